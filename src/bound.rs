@@ -8,10 +8,11 @@ use serde::{Deserialize, Serialize};
 /// # Generic
 ///
 /// - `T` - value type
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Bound<T> {
     pub min: T,
     pub max: T,
+    #[serde(default)]
     wrap: bool,
 }
 
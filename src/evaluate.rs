@@ -1,4 +1,18 @@
+use std::{rc::Rc, sync::Arc};
+
+pub mod any;
+pub mod bracket;
+pub mod combinator;
+pub mod compare;
+pub mod delimited;
+pub mod easing;
 pub mod expression;
+pub mod fit;
+pub mod grid;
+pub mod ordered_map;
+pub mod piecewise;
+pub mod stat_sheet;
+pub mod table;
 pub mod value_map;
 
 /// Evaluate from x to y
@@ -7,6 +21,95 @@ pub mod value_map;
 ///
 /// - `X` - from type
 /// - `Y` - to type
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+/// use flp_math::{evaluate::{self, expression}, growth::Growth, progress::Progress};
+/// let mut growth = Growth::<u8, u32, _>::new(
+///     evaluate::from_fn(|lvl: u8| lvl as u32 * 100),
+///     Progress::new(0, 0),
+/// );
+/// growth.apply_level(3);
+/// assert_eq!(*growth.progress().max(), 300);
+///
+/// fn double(lvl: u8) -> u32 {
+///     lvl as u32 * 2
+/// }
+/// let mut growth = Growth::<u8, u32, fn(u8) -> u32>::new(double, Progress::new(0, 0));
+/// growth.apply_level(3);
+/// assert_eq!(*growth.progress().max(), 6);
+///
+/// let shared = Rc::new(expression::Evaluate::try_from_str("(x * 10)").unwrap());
+/// let mut growths: Vec<_> = (0..2)
+///     .map(|_| Growth::<u8, u32, _>::new(Rc::clone(&shared), Progress::new(0, 0)))
+///     .collect();
+/// growths[1].apply_level(4);
+/// assert_eq!(*growths[1].progress().max(), 40);
+/// ```
 pub trait Evaluate<X, Y> {
     fn evaluate(&self, x: X) -> Y;
 }
+
+/// Evaluate with a closure
+pub struct FromFn<F>(pub F);
+
+/// Evaluate with a closure
+pub fn from_fn<X, Y, F>(f: F) -> FromFn<F>
+where
+    F: Fn(X) -> Y,
+{
+    FromFn(f)
+}
+
+impl<X, Y, F> Evaluate<X, Y> for FromFn<F>
+where
+    F: Fn(X) -> Y,
+{
+    fn evaluate(&self, x: X) -> Y {
+        (self.0)(x)
+    }
+}
+
+impl<X, Y> Evaluate<X, Y> for fn(X) -> Y {
+    fn evaluate(&self, x: X) -> Y {
+        self(x)
+    }
+}
+
+impl<X, Y, E> Evaluate<X, Y> for &E
+where
+    E: Evaluate<X, Y> + ?Sized,
+{
+    fn evaluate(&self, x: X) -> Y {
+        (**self).evaluate(x)
+    }
+}
+
+impl<X, Y, E> Evaluate<X, Y> for Box<E>
+where
+    E: Evaluate<X, Y> + ?Sized,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.as_ref().evaluate(x)
+    }
+}
+
+impl<X, Y, E> Evaluate<X, Y> for Rc<E>
+where
+    E: Evaluate<X, Y> + ?Sized,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.as_ref().evaluate(x)
+    }
+}
+
+impl<X, Y, E> Evaluate<X, Y> for Arc<E>
+where
+    E: Evaluate<X, Y> + ?Sized,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.as_ref().evaluate(x)
+    }
+}
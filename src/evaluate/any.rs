@@ -0,0 +1,260 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    hash::Hash,
+    marker::PhantomData,
+    str::FromStr,
+};
+
+use num_traits::{CheckedAdd, CheckedRem, CheckedSub, FromPrimitive, ToPrimitive};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+
+use super::{bracket, combinator, expression, ordered_map, piecewise, table, value_map};
+
+/// An evaluation inside another one
+type Nested<X, Y> = Box<Evaluate<X, Y>>;
+
+/// Any evaluation provided by this crate, chosen at runtime.
+///
+/// Serialized with the kind of evaluation in a `type` field, next to the fields of the evaluation.
+///
+/// Implements [`Evaluate`](super::Evaluate) between every pair of integer types up to 64 bits.
+/// Inputs are map keys and outputs are clamped by a [`Bound`](crate::bound::Bound), which both
+/// need integers, so floating point curves must use the evaluations directly.
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::{evaluate::{any, Evaluate}, growth::Growth, progress::Progress};
+/// let json = r#"[
+///     {"type": "expression", "source": "((x ^ 2) * 100)"},
+///     {"type": "table", "min": 1, "values": [100, 250, 500]},
+///     {"type": "ordered_map", "values": {"1": 100, "10": 1000}, "interpolation": "linear"},
+///     {"type": "bracket", "brackets": [[{"min": 1, "max": 9}, 1], [{"min": 10, "max": 99}, 2]]},
+///     {
+///         "type": "scale",
+///         "evaluate": {"type": "value_map", "values": {"1": 100, "2": 200}},
+///         "factor": 1.5
+///     },
+///     {
+///         "type": "fallback",
+///         "values": {"1": 10},
+///         "fallback": {"delegate": {"type": "expression", "source": "(x * 100)"}}
+///     }
+/// ]"#;
+/// let curves: Vec<any::Evaluate<u8, u32>> = serde_json::from_str(json).unwrap();
+/// let values: Vec<u32> = curves.iter().map(|curve| curve.evaluate(2)).collect();
+/// assert_eq!(values, vec![400, 250, 200, 1, 300, 200]);
+///
+/// let curve = curves.into_iter().next().unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&curve).unwrap(),
+///     r#"{"type":"expression","source":"((x ^ 2) * 100)"}"#
+/// );
+/// let mut growth = Growth::<u8, u32, _>::new(curve, Progress::new(0, 0));
+/// growth.apply_level(3);
+/// assert_eq!(*growth.progress().max(), 900);
+///
+/// let json = r#"{"type": "offset", "evaluate": {"type": "expression", "source": "(x * 2)"}, "offset": -50}"#;
+/// let signed: any::Evaluate<i16, i32> = serde_json::from_str(json).unwrap();
+/// assert_eq!(signed.evaluate(-10), -70);
+/// ```
+#[derive(Deserialize, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    bound(deserialize = "
+        X: Deserialize<'de>
+            + Clone
+            + Copy
+            + Display
+            + Eq
+            + Hash
+            + Ord
+            + CheckedAdd
+            + CheckedSub
+            + CheckedRem
+            + FromPrimitive
+            + FromStr
+            + ToPrimitive,
+        Y: Deserialize<'de> + ToPrimitive,
+        Evaluate<X, Y>: super::Evaluate<X, Y>,
+    ")
+)]
+pub enum Evaluate<X, Y>
+where
+    X: Eq + Hash + Ord,
+{
+    Expression {
+        source: expression::Evaluate,
+    },
+    Table(table::Evaluate<X, Y>),
+    ValueMap {
+        #[serde(deserialize_with = "value_map")]
+        values: value_map::Evaluate<X, Y>,
+    },
+    Fallback(#[serde(deserialize_with = "fallback")] value_map::WithFallback<X, Y, Nested<X, Y>>),
+    OrderedMap(#[serde(deserialize_with = "ordered_map")] ordered_map::Evaluate<X, Y>),
+    Bracket {
+        brackets: bracket::Evaluate<X, Y>,
+    },
+    Piecewise(piecewise::Evaluate<X, Y, Nested<X, Y>>),
+    Clamp(combinator::Clamp<Nested<X, Y>, Y>),
+    Scale(combinator::Scale<Nested<X, Y>>),
+    Offset(combinator::Offset<Nested<X, Y>, Y>),
+    Sum(combinator::Sum<Nested<X, Y>, Nested<X, Y>>),
+}
+
+/// Map key which may also be written as a string, since the buffering of the `type` field
+/// loses the hint that a JSON string key holds a number
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Key<X>(X);
+
+struct KeyVisitor<X>(PhantomData<X>);
+
+impl<'de, X> Visitor<'de> for KeyVisitor<X>
+where
+    X: FromPrimitive + FromStr,
+{
+    type Value = Key<X>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number or a string of a number")
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        X::from_u64(v)
+            .map(Key)
+            .ok_or_else(|| E::custom(format!("key {v} out of range")))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        X::from_i64(v)
+            .map(Key)
+            .ok_or_else(|| E::custom(format!("key {v} out of range")))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        v.parse()
+            .map(Key)
+            .map_err(|_| E::custom(format!("invalid key {v}")))
+    }
+}
+
+impl<'de, X> Deserialize<'de> for Key<X>
+where
+    X: FromPrimitive + FromStr,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(KeyVisitor(PhantomData))
+    }
+}
+
+fn keys<'de, D, X, Y>(deserializer: D) -> Result<HashMap<X, Y>, D::Error>
+where
+    D: Deserializer<'de>,
+    X: Eq + Hash + FromPrimitive + FromStr,
+    Y: Deserialize<'de>,
+{
+    let keys = HashMap::<Key<X>, Y>::deserialize(deserializer)?;
+    Ok(keys.into_iter().map(|(Key(x), y)| (x, y)).collect())
+}
+
+fn value_map<'de, D, X, Y>(deserializer: D) -> Result<value_map::Evaluate<X, Y>, D::Error>
+where
+    D: Deserializer<'de>,
+    X: Eq + Hash + FromPrimitive + FromStr,
+    Y: Deserialize<'de>,
+{
+    keys(deserializer).map(value_map::Evaluate)
+}
+
+#[derive(Deserialize)]
+#[serde(bound(
+    deserialize = "X: Eq + Hash + FromPrimitive + FromStr, Y: Deserialize<'de>, E: Deserialize<'de>"
+))]
+struct Fallback<X, Y, E>
+where
+    X: Eq + Hash,
+{
+    #[serde(deserialize_with = "value_map")]
+    values: value_map::Evaluate<X, Y>,
+    fallback: value_map::Fallback<Y, E>,
+}
+
+fn fallback<'de, D, X, Y, E>(deserializer: D) -> Result<value_map::WithFallback<X, Y, E>, D::Error>
+where
+    D: Deserializer<'de>,
+    X: Clone + Copy + Eq + Hash + FromPrimitive + FromStr + ToPrimitive,
+    Y: Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    let Fallback { values, fallback } = Fallback::deserialize(deserializer)?;
+    Ok(values.with_fallback(fallback))
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "X: Ord + FromPrimitive + FromStr, Y: Deserialize<'de>"))]
+struct OrderedMap<X, Y> {
+    values: BTreeMap<Key<X>, Y>,
+    #[serde(default)]
+    interpolation: ordered_map::Interpolation,
+}
+
+fn ordered_map<'de, D, X, Y>(deserializer: D) -> Result<ordered_map::Evaluate<X, Y>, D::Error>
+where
+    D: Deserializer<'de>,
+    X: Clone + Copy + Ord + FromPrimitive + FromStr,
+    Y: Deserialize<'de>,
+{
+    let OrderedMap {
+        values,
+        interpolation,
+    } = OrderedMap::deserialize(deserializer)?;
+    let values = values.into_iter().map(|(Key(x), y)| (x, y)).collect();
+    Ok(ordered_map::Evaluate::new(values, interpolation))
+}
+
+macro_rules! impl_any {
+    ($($x:ty),* => $ys:tt) => {
+        $(impl_any!(@each $x => $ys);)*
+    };
+    (@each $x:ty => ($($y:ty),*)) => {
+        $(
+        impl super::Evaluate<$x, $y> for Evaluate<$x, $y> {
+            fn evaluate(&self, x: $x) -> $y {
+                match self {
+                    Evaluate::Expression { source } => source.evaluate(x),
+                    Evaluate::Table(evaluate) => evaluate.evaluate(x),
+                    Evaluate::ValueMap { values } => values.evaluate(x),
+                    Evaluate::Fallback(evaluate) => evaluate.evaluate(x),
+                    Evaluate::OrderedMap(evaluate) => evaluate.evaluate(x),
+                    Evaluate::Bracket { brackets } => brackets.evaluate(x),
+                    Evaluate::Piecewise(evaluate) => evaluate.evaluate(x),
+                    Evaluate::Clamp(evaluate) => evaluate.evaluate(x),
+                    Evaluate::Scale(evaluate) => evaluate.evaluate(x),
+                    Evaluate::Offset(evaluate) => evaluate.evaluate(x),
+                    Evaluate::Sum(evaluate) => evaluate.evaluate(x),
+                }
+            }
+        }
+        )*
+    };
+}
+
+impl_any!(u8, u16, u32, u64, i8, i16, i32, i64 => (u8, u16, u32, u64, i8, i16, i32, i64));
@@ -0,0 +1,119 @@
+use std::fmt::Display;
+
+use num_traits::{CheckedAdd, CheckedRem, CheckedSub, FromPrimitive};
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::bound::Bound;
+
+/// Evaluate with values of consecutive integer ranges.
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::{bound::Bound, evaluate::{bracket, Evaluate}};
+/// let points = bracket::Evaluate::new(vec![
+///     (Bound::new(10u8, 19, false), 2u8),
+///     (Bound::new(1, 9, false), 1),
+///     (Bound::new(20, 99, false), 3),
+/// ])
+/// .unwrap();
+/// assert_eq!(points.evaluate(9), 1);
+/// assert_eq!(points.evaluate(10), 2);
+/// assert_eq!(points.evaluate(99), 3);
+///
+/// let gap = bracket::Evaluate::new(vec![(Bound::new(1u8, 9, false), 1u8), (Bound::new(11, 19, false), 2)]);
+/// assert_eq!(gap.err(), Some(String::from("Gap between brackets 1..=9 and 11..=19")));
+///
+/// let json = r#"[[{"min": 1, "max": 9}, 1], [{"min": 9, "max": 19}, 2]]"#;
+/// let overlap = serde_json::from_str::<bracket::Evaluate<u8, u8>>(json);
+/// assert!(overlap.unwrap_err().to_string().starts_with("Overlap between brackets 1..=9 and 9..=19"));
+///
+/// let json = r#"[[{"min": 9, "max": 1}, 1]]"#;
+/// let inverted = serde_json::from_str::<bracket::Evaluate<u8, u8>>(json);
+/// assert!(inverted.unwrap_err().to_string().starts_with("Invalid bracket 9..=1"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Evaluate<X, Y> {
+    brackets: Vec<(Bound<X>, Y)>,
+}
+
+impl<X, Y> Evaluate<X, Y>
+where
+    X: Clone + Copy + Display + PartialOrd + CheckedAdd + CheckedSub + CheckedRem + FromPrimitive,
+{
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// - `brackets` - ranges with their values, in any order, whose `wrap` is ignored
+    ///
+    /// # Errors
+    ///
+    /// If a range ends before it starts, there is a gap between two ranges or they overlap
+    pub fn new(mut brackets: Vec<(Bound<X>, Y)>) -> Result<Self, String> {
+        if let Some((bound, _)) = brackets.iter().find(|(bound, _)| bound.min > bound.max) {
+            return Err(format!("Invalid bracket {}..={}", bound.min, bound.max));
+        }
+        brackets.sort_by(|(a, _), (b, _)| {
+            a.min
+                .partial_cmp(&b.min)
+                .expect("Cannot compare bracket bounds")
+        });
+        let one = X::from_u8(1).unwrap();
+        for pair in brackets.windows(2) {
+            let (before, after) = (&pair[0].0, &pair[1].0);
+            let range = format!(
+                "{}..={} and {}..={}",
+                before.min, before.max, after.min, after.max
+            );
+            if after.min <= before.max {
+                return Err(format!("Overlap between brackets {range}"));
+            }
+            if before.max.checked_add(&one) != Some(after.min) {
+                return Err(format!("Gap between brackets {range}"));
+            }
+        }
+        Ok(Self { brackets })
+    }
+
+    /// Ranges with their values, in order
+    pub fn brackets(&self) -> &[(Bound<X>, Y)] {
+        &self.brackets
+    }
+}
+
+impl<'de, X, Y> Deserialize<'de> for Evaluate<X, Y>
+where
+    X: Clone
+        + Copy
+        + Display
+        + PartialOrd
+        + CheckedAdd
+        + CheckedSub
+        + CheckedRem
+        + FromPrimitive
+        + Deserialize<'de>,
+    Y: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(Vec::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl<X, Y> super::Evaluate<X, Y> for Evaluate<X, Y>
+where
+    X: Clone + Copy + Display + PartialOrd + CheckedAdd + CheckedSub + CheckedRem + FromPrimitive,
+    Y: Clone + Copy,
+{
+    fn evaluate(&self, x: X) -> Y {
+        let index = self.brackets.partition_point(|(bound, _)| bound.max < x);
+        match self.brackets.get(index) {
+            Some((bound, value)) if bound.min <= x => *value,
+            _ => panic!("Out of bound: Bracket"),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{fmt::Display, marker::PhantomData, ops::Add};
+
+use num_traits::{CheckedAdd, CheckedRem, CheckedSub, FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+use super::Evaluate;
+use crate::bound::Bound;
+
+/// Evaluate the output of `evaluate` with `map`
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Map<E, F, Y> {
+    #[serde(skip)]
+    y: PhantomData<Y>,
+    evaluate: E,
+    map: F,
+}
+
+impl<X, Y, Z, E, F> Evaluate<X, Z> for Map<E, F, Y>
+where
+    E: Evaluate<X, Y>,
+    F: Evaluate<Y, Z>,
+{
+    fn evaluate(&self, x: X) -> Z {
+        self.map.evaluate(self.evaluate.evaluate(x))
+    }
+}
+
+/// Evaluate `evaluate` with the output of `contramap`
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Contramap<E, F, X> {
+    #[serde(skip)]
+    x: PhantomData<X>,
+    evaluate: E,
+    contramap: F,
+}
+
+impl<W, X, Y, E, F> Evaluate<W, Y> for Contramap<E, F, X>
+where
+    E: Evaluate<X, Y>,
+    F: Evaluate<W, X>,
+{
+    fn evaluate(&self, w: W) -> Y {
+        self.evaluate.evaluate(self.contramap.evaluate(w))
+    }
+}
+
+/// Apply `bound` to the output of `evaluate`
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Clamp<E, Y> {
+    evaluate: E,
+    bound: Bound<Y>,
+}
+
+impl<X, Y, E> Evaluate<X, Y> for Clamp<E, Y>
+where
+    Y: Clone + Copy + Display + PartialOrd + CheckedAdd + CheckedSub + CheckedRem + FromPrimitive,
+    E: Evaluate<X, Y>,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.bound.apply(self.evaluate.evaluate(x))
+    }
+}
+
+/// Multiply the output of `evaluate` by `factor`
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Scale<E> {
+    evaluate: E,
+    factor: f64,
+}
+
+impl<X, Y, E> Evaluate<X, Y> for Scale<E>
+where
+    Y: ToPrimitive + FromPrimitive,
+    E: Evaluate<X, Y>,
+{
+    fn evaluate(&self, x: X) -> Y {
+        let y = self
+            .evaluate
+            .evaluate(x)
+            .to_f64()
+            .expect("Cannot convert Y to f64");
+        Y::from_f64(y * self.factor).expect("Cannot convert f64 to Y")
+    }
+}
+
+/// Add `offset` to the output of `evaluate`
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Offset<E, Y> {
+    evaluate: E,
+    offset: Y,
+}
+
+impl<X, Y, E> Evaluate<X, Y> for Offset<E, Y>
+where
+    Y: Clone + Copy + Add<Output = Y>,
+    E: Evaluate<X, Y>,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.evaluate.evaluate(x) + self.offset
+    }
+}
+
+/// Evaluate `left` and `right` with the same input, to a pair
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Zip<A, B> {
+    left: A,
+    right: B,
+}
+
+impl<X, Y, Z, A, B> Evaluate<X, (Y, Z)> for Zip<A, B>
+where
+    X: Clone + Copy,
+    A: Evaluate<X, Y>,
+    B: Evaluate<X, Z>,
+{
+    fn evaluate(&self, x: X) -> (Y, Z) {
+        (self.left.evaluate(x), self.right.evaluate(x))
+    }
+}
+
+/// Evaluate `left` and `right` with the same input, to the sum
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Sum<A, B> {
+    left: A,
+    right: B,
+}
+
+impl<X, Y, A, B> Evaluate<X, Y> for Sum<A, B>
+where
+    X: Clone + Copy,
+    Y: Add<Output = Y>,
+    A: Evaluate<X, Y>,
+    B: Evaluate<X, Y>,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.left.evaluate(x) + self.right.evaluate(x)
+    }
+}
+
+/// Combinators which wrap an evaluation into another one
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::{
+///     bound::Bound,
+///     evaluate::{combinator::{EvaluateExt, Offset, Scale}, expression, table, Evaluate},
+/// };
+/// let base = expression::Evaluate::try_from_str("(x * 10)").unwrap();
+/// let bonus = expression::Evaluate::try_from_str("(x % 3)").unwrap();
+/// let curve = table::Evaluate::<u8, u32>::precompute(&base, 1, 20)
+///     .scale(1.5)
+///     .offset(5)
+///     .sum(bonus)
+///     .clamp(Bound::new(0, 100, false));
+/// assert_eq!(curve.evaluate(4), 66);
+/// assert_eq!(curve.evaluate(9), 100);
+/// assert_eq!(curve.clone().zip(curve.clone()).evaluate(4), (66, 66));
+///
+/// let level = expression::Evaluate::try_from_str("(x + 1)").unwrap();
+/// assert_eq!(curve.contramap::<u8, _>(level).evaluate(3), 66);
+///
+/// let json = r#"{"evaluate": {"evaluate": "(x * 10)", "factor": 1.5}, "offset": 5}"#;
+/// let curve: Offset<Scale<expression::Evaluate>, u32> = serde_json::from_str(json).unwrap();
+/// assert_eq!(Evaluate::<u8, u32>::evaluate(&curve, 4), 65);
+/// ```
+pub trait EvaluateExt<X, Y>: Evaluate<X, Y> + Sized {
+    /// Evaluate the output with `map`
+    fn map<Z, F>(self, map: F) -> Map<Self, F, Y>
+    where
+        F: Evaluate<Y, Z>,
+    {
+        Map {
+            y: PhantomData,
+            evaluate: self,
+            map,
+        }
+    }
+
+    /// Evaluate the output with `next`, the same as [`EvaluateExt::map`]
+    fn and_then<Z, F>(self, next: F) -> Map<Self, F, Y>
+    where
+        F: Evaluate<Y, Z>,
+    {
+        self.map(next)
+    }
+
+    /// Evaluate the output of `contramap` instead of the input
+    fn contramap<W, F>(self, contramap: F) -> Contramap<Self, F, X>
+    where
+        F: Evaluate<W, X>,
+    {
+        Contramap {
+            x: PhantomData,
+            evaluate: self,
+            contramap,
+        }
+    }
+
+    /// Apply `bound` to the output
+    fn clamp(self, bound: Bound<Y>) -> Clamp<Self, Y> {
+        Clamp {
+            evaluate: self,
+            bound,
+        }
+    }
+
+    /// Multiply the output by `factor`
+    fn scale(self, factor: f64) -> Scale<Self> {
+        Scale {
+            evaluate: self,
+            factor,
+        }
+    }
+
+    /// Add `offset` to the output
+    fn offset(self, offset: Y) -> Offset<Self, Y> {
+        Offset {
+            evaluate: self,
+            offset,
+        }
+    }
+
+    /// Evaluate both with the same input, to a pair
+    fn zip<Z, B>(self, right: B) -> Zip<Self, B>
+    where
+        B: Evaluate<X, Z>,
+    {
+        Zip { left: self, right }
+    }
+
+    /// Evaluate both with the same input, to the sum
+    fn sum<B>(self, right: B) -> Sum<Self, B>
+    where
+        B: Evaluate<X, Y>,
+    {
+        Sum { left: self, right }
+    }
+}
+
+impl<X, Y, E> EvaluateExt<X, Y> for E where E: Evaluate<X, Y> {}
@@ -0,0 +1,193 @@
+use std::fmt::{self, Display, Formatter};
+
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+/// Values of two curves at one input
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Point<X> {
+    pub x: X,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl<X> Point<X> {
+    /// `after - before`
+    pub fn difference(&self) -> f64 {
+        self.after - self.before
+    }
+
+    /// Difference relative to `before` in percent, `None` if `before` is zero
+    pub fn percent(&self) -> Option<f64> {
+        if self.before == 0.0 {
+            None
+        } else {
+            Some(self.difference() / self.before.abs() * 100.0)
+        }
+    }
+}
+
+/// Point by point comparison of two curves over a domain
+///
+/// Displays as a text table, see [`Comparison::to_csv`] for CSV.
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{compare::Comparison, expression};
+/// let before = expression::Evaluate::try_from_str("(x * 10)").unwrap();
+/// let after = expression::Evaluate::try_from_str("((x ^ 2) + 16)").unwrap();
+/// let comparison = Comparison::<u8>::new::<_, _, u32>(&before, &after, 1..=9);
+///
+/// let max = comparison.max_deviation().unwrap();
+/// assert_eq!((max.x, max.difference()), (5, -9.0));
+/// assert_eq!(comparison.crossovers(), vec![2, 8]);
+///
+/// let csv = comparison.to_csv();
+/// assert_eq!(csv.lines().next(), Some("x,before,after,difference,percent"));
+/// assert_eq!(csv.lines().nth(4), Some("4,40,32,-8,-20"));
+///
+/// let table = comparison.to_string();
+/// assert_eq!(table.lines().nth(1), Some("1      10     17          +7  +70.00%"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Comparison<X> {
+    points: Vec<Point<X>>,
+}
+
+impl<X> Comparison<X>
+where
+    X: Clone + Copy,
+{
+    /// Evaluate `before` and `after` once for every input in `domain`
+    ///
+    /// # Panic
+    ///
+    /// If a value cannot be represented as `f64`
+    pub fn new<A, B, Y>(before: &A, after: &B, domain: impl IntoIterator<Item = X>) -> Self
+    where
+        A: super::Evaluate<X, Y>,
+        B: super::Evaluate<X, Y>,
+        Y: ToPrimitive,
+    {
+        let points = domain
+            .into_iter()
+            .map(|x| Point {
+                x,
+                before: before.evaluate(x).to_f64().expect("Cannot compare value"),
+                after: after.evaluate(x).to_f64().expect("Cannot compare value"),
+            })
+            .collect();
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[Point<X>] {
+        &self.points
+    }
+
+    /// The point with the largest absolute difference, the first one on ties
+    pub fn max_deviation(&self) -> Option<&Point<X>> {
+        self.points.iter().fold(None, |max, point| match max {
+            Some(max) if max.difference().abs() >= point.difference().abs() => Some(max),
+            _ => Some(point),
+        })
+    }
+
+    /// Inputs at which `after` moves to the other side of `before`
+    ///
+    /// If the curves meet before parting, the first input where they are equal is reported.
+    pub fn crossovers(&self) -> Vec<X> {
+        let mut side = None;
+        let mut equal = None;
+        let mut crossovers = Vec::new();
+        for point in &self.points {
+            let difference = point.difference();
+            if difference.is_nan() {
+                continue;
+            }
+            if difference == 0.0 {
+                equal = equal.or(Some(point.x));
+                continue;
+            }
+            let positive = difference > 0.0;
+            if side.is_some_and(|side| side != positive) {
+                crossovers.push(equal.unwrap_or(point.x));
+            }
+            side = Some(positive);
+            equal = None;
+        }
+        crossovers
+    }
+}
+
+impl<X> Comparison<X>
+where
+    X: Display,
+{
+    /// CSV with a header line, the percent is left empty when `before` is zero
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("x,before,after,difference,percent\n");
+        for point in &self.points {
+            let percent = point
+                .percent()
+                .map(|percent| percent.to_string())
+                .unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                point.x,
+                point.before,
+                point.after,
+                point.difference(),
+                percent
+            ));
+        }
+        csv
+    }
+}
+
+impl<X> Display for Comparison<X>
+where
+    X: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let header = ["x", "before", "after", "difference", "percent"];
+        let rows: Vec<[String; 5]> = self
+            .points
+            .iter()
+            .map(|point| {
+                [
+                    point.x.to_string(),
+                    point.before.to_string(),
+                    point.after.to_string(),
+                    format!("{:+}", point.difference()),
+                    point
+                        .percent()
+                        .map(|percent| format!("{percent:+.2}%"))
+                        .unwrap_or_else(|| String::from("-")),
+                ]
+            })
+            .collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].len())
+                    .chain([header[column].len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let line = |f: &mut Formatter<'_>, cells: &[&str]| {
+            let cells: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:>width$}"))
+                .collect();
+            writeln!(f, "{}", cells.join("  "))
+        };
+        line(f, &header)?;
+        for row in &rows {
+            line(f, &row.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::Display,
+    hash::Hash,
+    str::FromStr,
+};
+
+use num_traits::ToPrimitive;
+
+use super::value_map;
+
+/// Delimiter of comma separated values
+pub const CSV: char = ',';
+/// Delimiter of tab separated values
+pub const TSV: char = '\t';
+
+/// Cell of delimited text
+pub(super) struct Cell {
+    pub text: String,
+    /// Whether the cell was in double quotes, which makes it present even if empty
+    pub quoted: bool,
+}
+
+/// Records of `s` split into cells at `delimiter`, with the line number each starts on,
+/// starting at 1
+///
+/// A cell in double quotes may contain the delimiter, line breaks, and `""` for a quote.
+/// Surrounding whitespace is removed from other cells, and blank lines are skipped.
+pub(super) fn records(s: &str, delimiter: char) -> Result<Vec<(usize, Vec<Cell>)>, String> {
+    let mut records = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let (mut quoted, mut in_quotes) = (false, false);
+    let (mut line_number, mut start) = (1, 1);
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if !quoted && cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+                in_quotes = true;
+            }
+            '\n' if !in_quotes => {
+                cells.push(finish(&mut cell, quoted));
+                push_record(&mut records, start, std::mem::take(&mut cells));
+                quoted = false;
+                line_number += 1;
+                start = line_number;
+            }
+            c if c == delimiter && !in_quotes => {
+                cells.push(finish(&mut cell, quoted));
+                quoted = false;
+            }
+            c if quoted && !in_quotes && c.is_whitespace() => {}
+            c => {
+                if c == '\n' {
+                    line_number += 1;
+                }
+                cell.push(c);
+            }
+        }
+    }
+    if in_quotes {
+        return Err(format!("line {start}: unclosed quote"));
+    }
+    cells.push(finish(&mut cell, quoted));
+    push_record(&mut records, start, cells);
+    Ok(records)
+}
+
+fn push_record(records: &mut Vec<(usize, Vec<Cell>)>, line_number: usize, cells: Vec<Cell>) {
+    let blank = matches!(&cells[..], [cell] if !cell.quoted && cell.text.is_empty());
+    if !blank {
+        records.push((line_number, cells));
+    }
+}
+
+fn finish(cell: &mut String, quoted: bool) -> Cell {
+    let cell = std::mem::take(cell);
+    Cell {
+        text: if quoted {
+            cell
+        } else {
+            cell.trim().to_string()
+        },
+        quoted,
+    }
+}
+
+/// `cell` in double quotes if it would not read back as itself
+fn quote(cell: String, delimiter: char) -> String {
+    let plain =
+        !cell.is_empty() && !cell.contains([delimiter, '"', '\n', '\r']) && cell.trim() == cell;
+    if plain {
+        cell
+    } else {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    }
+}
+
+/// Rows of `x, y` cells with the line numbers they start on, starting at 1
+///
+/// The first row is skipped as a header if its key does not parse.
+fn rows<X>(s: &str, delimiter: char) -> Result<Vec<(usize, X, Cell)>, String>
+where
+    X: FromStr,
+{
+    let mut rows = Vec::new();
+    for (index, (line_number, cells)) in records(s, delimiter)?.into_iter().enumerate() {
+        let [key, value] = <[Cell; 2]>::try_from(cells).map_err(|cells| {
+            format!(
+                "line {line_number}: expected 2 columns, found {}",
+                cells.len()
+            )
+        })?;
+        match key.text.parse() {
+            Ok(key) => rows.push((line_number, key, value)),
+            Err(_) if index == 0 => continue,
+            Err(_) => {
+                return Err(format!(
+                    "line {line_number}: cannot parse key `{}`",
+                    key.text
+                ))
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// Read a value map from delimited text with one `x, y` row per line, except for line breaks
+/// in double quotes
+///
+/// # Errors
+///
+/// With the line number if a row does not have 2 columns, a cell does not parse, a key
+/// is repeated, or a quote is not closed
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{delimited, Evaluate};
+/// let map = delimited::read::<u8, u32>("level,exp\n1,100\n2,250\n", delimited::CSV).unwrap();
+/// assert_eq!(map.evaluate(2), 250);
+/// assert_eq!(
+///     delimited::read::<u8, u32>("1\t100\n2\tmany\n", delimited::TSV).err(),
+///     Some(String::from("line 2: cannot parse value `many`"))
+/// );
+/// assert_eq!(
+///     delimited::read::<u8, u32>("1,100\n\n1,250\n", delimited::CSV).err(),
+///     Some(String::from("line 3: duplicate key 1, first on line 1"))
+/// );
+/// ```
+pub fn read<X, Y>(s: &str, delimiter: char) -> Result<value_map::Evaluate<X, Y>, String>
+where
+    X: Clone + Display + FromStr + Eq + Hash,
+    Y: FromStr,
+{
+    let mut values = HashMap::new();
+    let mut lines = HashMap::new();
+    for (line_number, key, value) in rows::<X>(s, delimiter)? {
+        if value.text.is_empty() && !value.quoted {
+            return Err(format!("line {line_number}: missing value"));
+        }
+        let value = value
+            .text
+            .parse()
+            .map_err(|_| format!("line {line_number}: cannot parse value `{}`", value.text))?;
+        match lines.entry(key) {
+            Entry::Occupied(entry) => {
+                return Err(format!(
+                    "line {line_number}: duplicate key {}, first on line {}",
+                    entry.key(),
+                    entry.get()
+                ))
+            }
+            Entry::Vacant(entry) => {
+                values.insert(entry.key().clone(), value);
+                entry.insert(line_number);
+            }
+        }
+    }
+    Ok(value_map::Evaluate(values))
+}
+
+/// Read a value map like [`read`], also requiring the keys to be consecutive integers
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::delimited;
+/// assert_eq!(
+///     delimited::read_contiguous::<u8, u32>("1,100\n2,250\n4,600\n", delimited::CSV).err(),
+///     Some(String::from("line 3: missing row for key 3"))
+/// );
+/// ```
+pub fn read_contiguous<X, Y>(s: &str, delimiter: char) -> Result<value_map::Evaluate<X, Y>, String>
+where
+    X: Clone + Display + FromStr + Eq + Hash + ToPrimitive,
+    Y: FromStr,
+{
+    let map = read(s, delimiter)?;
+    let mut keys: Vec<(i128, usize)> = rows::<X>(s, delimiter)?
+        .into_iter()
+        .map(|(line_number, key, _)| {
+            key.to_i128()
+                .map(|key| (key, line_number))
+                .ok_or_else(|| format!("line {line_number}: key {key} is not an integer"))
+        })
+        .collect::<Result<_, _>>()?;
+    keys.sort();
+    for pair in keys.windows(2) {
+        if pair[1].0 != pair[0].0 + 1 {
+            return Err(format!(
+                "line {}: missing row for key {}",
+                pair[1].1,
+                pair[0].0 + 1
+            ));
+        }
+    }
+    Ok(map)
+}
+
+/// Write a value map as delimited text with a `x, y` header, ordered by key
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use flp_math::evaluate::{delimited, value_map};
+/// let map = value_map::Evaluate(HashMap::from([(2u8, 250u32), (1, 100)]));
+/// assert_eq!(delimited::write(&map, delimited::TSV), "x\ty\n1\t100\n2\t250\n");
+///
+/// let names = value_map::Evaluate(HashMap::from([(1u8, String::from("Squire, \"Sir\""))]));
+/// let csv = delimited::write(&names, delimited::CSV);
+/// assert_eq!(csv, "x,y\n1,\"Squire, \"\"Sir\"\"\"\n");
+/// assert_eq!(delimited::read::<u8, String>(&csv, delimited::CSV).unwrap().0, names.0);
+///
+/// let notes = HashMap::from([(1u8, String::from("a\nb")), (2, String::new())]);
+/// let notes = value_map::Evaluate(notes);
+/// let csv = delimited::write(&notes, delimited::CSV);
+/// assert_eq!(csv, "x,y\n1,\"a\nb\"\n2,\"\"\n");
+/// assert_eq!(delimited::read::<u8, String>(&csv, delimited::CSV).unwrap().0, notes.0);
+/// assert_eq!(
+///     delimited::read::<u8, String>("1,\"a\n2,b\n", delimited::CSV).err(),
+///     Some(String::from("line 1: unclosed quote"))
+/// );
+/// ```
+pub fn write<X, Y>(map: &value_map::Evaluate<X, Y>, delimiter: char) -> String
+where
+    X: Display + Eq + Hash + Ord,
+    Y: Display,
+{
+    let mut rows: Vec<(&X, &Y)> = map.0.iter().collect();
+    rows.sort_by_key(|(x, _)| *x);
+    write_rows(rows, delimiter)
+}
+
+/// Evaluate `evaluate` once for every input in `domain` and write the rows like [`write`]
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{delimited, expression};
+/// let curve = expression::Evaluate::try_from_str("(x * 10)").unwrap();
+/// let csv = delimited::sample::<_, u8, u16>(&curve, 1..=3, delimited::CSV);
+/// assert_eq!(csv, "x,y\n1,10\n2,20\n3,30\n");
+/// ```
+pub fn sample<E, X, Y>(evaluate: &E, domain: impl IntoIterator<Item = X>, delimiter: char) -> String
+where
+    E: super::Evaluate<X, Y>,
+    X: Clone + Copy + Display,
+    Y: Display,
+{
+    let rows: Vec<(X, Y)> = domain
+        .into_iter()
+        .map(|x| (x, evaluate.evaluate(x)))
+        .collect();
+    write_rows(rows, delimiter)
+}
+
+fn write_rows<X, Y>(rows: impl IntoIterator<Item = (X, Y)>, delimiter: char) -> String
+where
+    X: Display,
+    Y: Display,
+{
+    let mut s = format!("x{delimiter}y\n");
+    for (x, y) in rows {
+        let (x, y) = (
+            quote(x.to_string(), delimiter),
+            quote(y.to_string(), delimiter),
+        );
+        s.push_str(&format!("{x}{delimiter}{y}\n"));
+    }
+    s
+}
@@ -0,0 +1,302 @@
+use std::f64::consts::PI;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+/// Shape of the start of an easing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    Linear,
+    Quad,
+    Cubic,
+    Expo,
+    Elastic,
+    Back,
+    Bounce,
+}
+
+impl Curve {
+    /// The easing in, from which out and in-out are derived
+    fn ease_in(&self, t: f64) -> f64 {
+        match self {
+            Curve::Linear => t,
+            Curve::Quad => t * t,
+            Curve::Cubic => t * t * t,
+            Curve::Expo => {
+                if t == 0.0 {
+                    0.0
+                } else {
+                    2f64.powf(10.0 * t - 10.0)
+                }
+            }
+            Curve::Elastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -(2f64.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * 2.0 * PI / 3.0).sin()
+                }
+            }
+            Curve::Back => {
+                let overshoot = 1.70158;
+                (overshoot + 1.0) * t * t * t - overshoot * t * t
+            }
+            Curve::Bounce => 1.0 - bounce_out(1.0 - t),
+        }
+    }
+
+    /// The easing in then out, with a larger overshoot for back and a shorter period for elastic
+    fn ease_in_out(&self, t: f64) -> f64 {
+        match self {
+            Curve::Back => {
+                let overshoot = 1.70158 * 1.525;
+                if t < 0.5 {
+                    let t = 2.0 * t;
+                    t * t * ((overshoot + 1.0) * t - overshoot) / 2.0
+                } else {
+                    let t = 2.0 * t - 2.0;
+                    (t * t * ((overshoot + 1.0) * t + overshoot) + 2.0) / 2.0
+                }
+            }
+            Curve::Elastic => {
+                let wave = ((20.0 * t - 11.125) * 2.0 * PI / 4.5).sin();
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    -(2f64.powf(20.0 * t - 10.0)) * wave / 2.0
+                } else {
+                    2f64.powf(10.0 - 20.0 * t) * wave / 2.0 + 1.0
+                }
+            }
+            _ => {
+                if t < 0.5 {
+                    self.ease_in(2.0 * t) / 2.0
+                } else {
+                    1.0 - self.ease_in(2.0 - 2.0 * t) / 2.0
+                }
+            }
+        }
+    }
+}
+
+fn bounce_out(t: f64) -> f64 {
+    let (n, d) = (7.5625, 2.75);
+    if t < 1.0 / d {
+        n * t * t
+    } else if t < 2.0 / d {
+        let t = t - 1.5 / d;
+        n * t * t + 0.75
+    } else if t < 2.5 / d {
+        let t = t - 2.25 / d;
+        n * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d;
+        n * t * t + 0.984375
+    }
+}
+
+/// Which end of an easing the curve applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    In,
+    Out,
+    InOut,
+}
+
+/// Evaluate with an easing from 0 to 1 over the input domain 0..=1.
+///
+/// Inputs outside the domain are clamped.
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{easing::{self, Curve, Mode}, Evaluate};
+/// let quad_in = easing::Evaluate::new(Curve::Quad, Mode::In);
+/// assert_eq!(quad_in.evaluate(0.5), 0.25);
+/// let quad_out = easing::Evaluate::new(Curve::Quad, Mode::Out);
+/// assert_eq!(quad_out.evaluate(0.5), 0.75);
+/// let bounce = easing::Evaluate::new(Curve::Bounce, Mode::InOut);
+/// assert_eq!((bounce.evaluate(0.0), bounce.evaluate(0.5), bounce.evaluate(1.0)), (0.0, 0.5, 1.0));
+/// let back = easing::Evaluate::new(Curve::Back, Mode::In);
+/// assert!(back.evaluate(0.2) < 0.0);
+/// let back = easing::Evaluate::new(Curve::Back, Mode::InOut);
+/// assert!((back.evaluate(0.25) + 0.0997).abs() < 1e-4);
+/// let elastic = easing::Evaluate::new(Curve::Elastic, Mode::InOut);
+/// assert!((elastic.evaluate(0.25) - 0.0119).abs() < 1e-4);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Evaluate {
+    pub curve: Curve,
+    pub mode: Mode,
+}
+
+impl Evaluate {
+    pub fn new(curve: Curve, mode: Mode) -> Self {
+        Self { curve, mode }
+    }
+}
+
+impl super::Evaluate<f64, f64> for Evaluate {
+    fn evaluate(&self, x: f64) -> f64 {
+        let t = x.clamp(0.0, 1.0);
+        match self.mode {
+            Mode::In => self.curve.ease_in(t),
+            Mode::Out => 1.0 - self.curve.ease_in(1.0 - t),
+            Mode::InOut => self.curve.ease_in_out(t),
+        }
+    }
+}
+
+/// Easing along a cubic Bézier curve from `(0, 0)` to `(1, 1)`, like CSS `cubic-bezier()`
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{easing::CubicBezier, Evaluate};
+/// let ease = CubicBezier::new(0.25, 0.1, 0.25, 1.0);
+/// assert!((ease.evaluate(0.5) - 0.8024).abs() < 1e-4);
+/// let linear = CubicBezier::new(0.0, 0.0, 1.0, 1.0);
+/// assert!((linear.evaluate(0.3) - 0.3).abs() < 1e-9);
+///
+/// let json = r#"{"x1": 5, "y1": 0, "x2": -3, "y2": 1}"#;
+/// assert!(serde_json::from_str::<CubicBezier>(json).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CubicBezier {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+}
+
+impl CubicBezier {
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// - `x1`, `y1` - the first control point
+    /// - `x2`, `y2` - the second control point
+    ///
+    /// # Panic
+    ///
+    /// If `x1` or `x2` is outside 0..=1, which would make the curve not a function of x
+    pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+        if let Err(err) = Self::check(x1, x2) {
+            panic!("{err}");
+        }
+        Self { x1, y1, x2, y2 }
+    }
+
+    fn check(x1: f64, x2: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+            return Err(String::from(
+                "Invalid cubic bezier: x of control points must be in 0..=1",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Coordinate at `t` of a curve from 0 to 1 with control coordinates `a` and `b`
+    fn at(a: f64, b: f64, t: f64) -> f64 {
+        let u = 1.0 - t;
+        3.0 * u * u * t * a + 3.0 * u * t * t * b + t * t * t
+    }
+
+    fn slope(a: f64, b: f64, t: f64) -> f64 {
+        let u = 1.0 - t;
+        3.0 * u * u * a + 6.0 * u * t * (b - a) + 3.0 * t * t * (1.0 - b)
+    }
+
+    /// Parameter at which the curve reaches `x`
+    fn solve(&self, x: f64) -> f64 {
+        let mut t = x;
+        for _ in 0..8 {
+            let error = Self::at(self.x1, self.x2, t) - x;
+            if error.abs() < 1e-12 {
+                return t;
+            }
+            let slope = Self::slope(self.x1, self.x2, t);
+            if slope.abs() < 1e-9 {
+                break;
+            }
+            t -= error / slope;
+        }
+        let (mut low, mut high) = (0.0, 1.0);
+        t = x;
+        for _ in 0..64 {
+            if Self::at(self.x1, self.x2, t) < x {
+                low = t;
+            } else {
+                high = t;
+            }
+            t = (low + high) / 2.0;
+        }
+        t
+    }
+}
+
+impl<'de> Deserialize<'de> for CubicBezier {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Points {
+            x1: f64,
+            y1: f64,
+            x2: f64,
+            y2: f64,
+        }
+        let Points { x1, y1, x2, y2 } = Points::deserialize(deserializer)?;
+        Self::check(x1, x2).map_err(de::Error::custom)?;
+        Ok(Self { x1, y1, x2, y2 })
+    }
+}
+
+impl super::Evaluate<f64, f64> for CubicBezier {
+    fn evaluate(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        Self::at(self.y1, self.y2, self.solve(x))
+    }
+}
+
+/// Stretch an easing over the domain `from` and the range `to`
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{easing::{self, Curve, Mode, Remap}, Evaluate};
+/// let bar = Remap::new(easing::Evaluate::new(Curve::Cubic, Mode::Out), (0.0, 100.0), (0.0, 300.0));
+/// assert_eq!(bar.evaluate(0.0), 0.0);
+/// assert_eq!(bar.evaluate(50.0), 262.5);
+/// assert_eq!(bar.evaluate(150.0), 300.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Remap<E> {
+    evaluate: E,
+    from: (f64, f64),
+    to: (f64, f64),
+}
+
+impl<E> Remap<E> {
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// - `evaluate` - the easing on the domain 0..=1
+    /// - `from` - the input which maps to 0 and the input which maps to 1
+    /// - `to` - the output 0 maps to and the output 1 maps to
+    pub fn new(evaluate: E, from: (f64, f64), to: (f64, f64)) -> Self {
+        Self { evaluate, from, to }
+    }
+}
+
+impl<E> super::Evaluate<f64, f64> for Remap<E>
+where
+    E: super::Evaluate<f64, f64>,
+{
+    fn evaluate(&self, x: f64) -> f64 {
+        let t = (x - self.from.0) / (self.from.1 - self.from.0);
+        self.to.0 + (self.to.1 - self.to.0) * self.evaluate.evaluate(t)
+    }
+}
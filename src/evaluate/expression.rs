@@ -1,20 +1,49 @@
-use core::fmt;
+use core::fmt::{self, Display};
+use std::{
+    cell::{RefCell, RefMut},
+    collections::BTreeSet,
+    hash::{Hash, Hasher},
+    mem,
+};
 
 use num_traits::{FromPrimitive, ToPrimitive};
+use rand::{Rng, RngCore};
 use serde::{
-    de::{self, Visitor},
+    de::{self, DeserializeSeed, Visitor},
     Deserialize, Deserializer, Serialize, Serializer,
 };
 
 use self::{
     atom::Variable,
     operation::Operation::{self, *},
+    registry::HostFunction,
+    scope::{Scope, INPUT},
+};
+
+pub use self::{
+    analysis::Analysis,
+    diagnostic::{Diagnostic, DiagnosticKind, Evaluation, Span},
+    explain::Explanation,
+    lint::{Lint, LintKind, Severity},
+    registry::FunctionRegistry,
 };
 
+mod analysis;
 mod atom;
+mod canonical;
+mod diagnostic;
+mod explain;
+mod lint;
 mod operation;
+mod partial;
+mod registry;
+pub mod rpn;
+mod scope;
+pub mod sexpr;
+mod summation;
+pub mod tree;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct BinaryOperation {
     left: Box<Evaluate>,
     right: Box<Evaluate>,
@@ -29,7 +58,35 @@ impl BinaryOperation {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct LetBinding {
+    name: String,
+    value: Box<Evaluate>,
+    body: Box<Evaluate>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct FunctionDefinition {
+    name: String,
+    params: Vec<String>,
+    value: Box<Evaluate>,
+    body: Box<Evaluate>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct FunctionCall {
+    name: String,
+    args: Vec<Evaluate>,
+    host: Option<HostFunction>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct DiceRoll {
+    count: u32,
+    sides: u32,
+}
+
+#[derive(Clone, Debug)]
 enum Node {
     Add(BinaryOperation),
     Sub(BinaryOperation),
@@ -39,7 +96,77 @@ enum Node {
     Pow(BinaryOperation),
     Log(BinaryOperation),
     Number(f64),
-    Variable,
+    Variable(String),
+    Let(LetBinding),
+    Def(FunctionDefinition),
+    Call(FunctionCall),
+    Rand(BinaryOperation),
+    RandInt(BinaryOperation),
+    Dice(DiceRoll),
+}
+
+impl Node {
+    /// Apply an arithmetic operation to the values of its operands
+    fn apply(&self, left: f64, right: f64) -> f64 {
+        match self {
+            Node::Add(_) => left + right,
+            Node::Sub(_) => left - right,
+            Node::Mul(_) => left * right,
+            Node::Div(_) => left / right,
+            Node::Mod(_) => left % right,
+            Node::Pow(_) => left.powf(right),
+            Node::Log(_) => left.log(right),
+            _ => panic!("Not an arithmetic operation"),
+        }
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Node::Add(left), Node::Add(right))
+            | (Node::Sub(left), Node::Sub(right))
+            | (Node::Mul(left), Node::Mul(right))
+            | (Node::Div(left), Node::Div(right))
+            | (Node::Mod(left), Node::Mod(right))
+            | (Node::Pow(left), Node::Pow(right))
+            | (Node::Log(left), Node::Log(right))
+            | (Node::Rand(left), Node::Rand(right))
+            | (Node::RandInt(left), Node::RandInt(right)) => left == right,
+            (Node::Number(left), Node::Number(right)) => left.to_bits() == right.to_bits(),
+            (Node::Variable(left), Node::Variable(right)) => left == right,
+            (Node::Let(left), Node::Let(right)) => left == right,
+            (Node::Def(left), Node::Def(right)) => left == right,
+            (Node::Call(left), Node::Call(right)) => left == right,
+            (Node::Dice(left), Node::Dice(right)) => left == right,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Node {}
+
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Node::Add(node)
+            | Node::Sub(node)
+            | Node::Mul(node)
+            | Node::Div(node)
+            | Node::Mod(node)
+            | Node::Pow(node)
+            | Node::Log(node)
+            | Node::Rand(node)
+            | Node::RandInt(node) => node.hash(state),
+            Node::Number(node) => node.to_bits().hash(state),
+            Node::Variable(node) => node.hash(state),
+            Node::Let(node) => node.hash(state),
+            Node::Def(node) => node.hash(state),
+            Node::Call(node) => node.hash(state),
+            Node::Dice(node) => node.hash(state),
+        }
+    }
 }
 
 /// Evaluate a math expression
@@ -56,33 +183,479 @@ enum Node {
 /// let evaluate = Evaluate::try_from_str("(((2 * (x ^2))-((6/ x) +(25 log 5)))%80)").unwrap();
 /// assert_eq!(Super::<u8, u8>::evaluate(&evaluate, 7), 15);
 /// ```
-#[derive(Debug)]
+///
+/// Every operation must be wrapped in its own parentheses, as in `((x * 2) + 1)`.
+///
+/// Repeated sub-expressions can be bound once with `let`, and small helpers
+/// can be defined with `def`. Each statement ends with `;` and is visible to
+/// everything after it.
+///
+/// ```
+/// use flp_math::evaluate::Evaluate as Super;
+/// use flp_math::evaluate::expression::Evaluate;
+/// let evaluate =
+///     Evaluate::try_from_str("let base = (x ^ 2); ((base * 100) + (base / 4))").unwrap();
+/// assert_eq!(Super::<u8, u32>::evaluate(&evaluate, 2), 401);
+/// let evaluate = Evaluate::try_from_str("def f(a) = (a * 2); (f(x) + f((x + 1)))").unwrap();
+/// assert_eq!(Super::<u8, u8>::evaluate(&evaluate, 3), 14);
+/// assert_eq!(
+///     evaluate.to_string(),
+///     "def f(a) = (a * 2); (f(x) + f((x + 1)))"
+/// );
+/// assert!(Evaluate::try_from_str("(y + 1)").is_err());
+/// assert_eq!(
+///     Evaluate::try_from_str("let a = (x + 1); (a * 2) + 3").err(),
+///     Some(String::from("Unexpected input: + 3"))
+/// );
+/// assert!(Evaluate::try_from_str("5 6").is_err());
+/// assert!(Evaluate::try_from_str("def f(a) = (a * 2); f(x, 1)").is_err());
+/// assert_eq!(
+///     Evaluate::try_from_str("let nan = 5; (nan + x)").err(),
+///     Some(String::from("Invalid variable name: nan"))
+/// );
+/// assert_eq!(
+///     Evaluate::try_from_str("def f(inf) = (inf * 2); f(x)").err(),
+///     Some(String::from("Invalid variable name: inf"))
+/// );
+/// ```
+///
+/// Random terms `rand(low, high)`, `randint(low, high)` and dice `NdM` are
+/// rolled with a caller supplied generator, so results can be reproduced from a seed.
+/// They are only parsed with a registry which allows them.
+///
+/// ```
+/// use flp_math::evaluate::expression::{Evaluate, FunctionRegistry};
+/// use rand::{rngs::StdRng, SeedableRng};
+/// assert!(Evaluate::try_from_str("(3d6 + x)").is_err());
+/// let mut registry = FunctionRegistry::new();
+/// registry.allow_random();
+/// let evaluate = Evaluate::try_from_str_with("(3d6 + (x / 2))", &registry).unwrap();
+/// let first: u8 = evaluate.evaluate_with_rng(10, &mut StdRng::seed_from_u64(7));
+/// let second: u8 = evaluate.evaluate_with_rng(10, &mut StdRng::seed_from_u64(7));
+/// assert_eq!(first, second);
+/// assert!((8..=23).contains(&first));
+/// let analysis = evaluate.analyze(10);
+/// assert_eq!((analysis.min, analysis.max, analysis.expected), (8.0, 23.0, 15.5));
+/// let analysis = Evaluate::try_from_str_with("((1d6 - 3) ^ 2)", &registry).unwrap().analyze(0);
+/// assert_eq!((analysis.min, analysis.max), (0.0, 9.0));
+/// let analysis = Evaluate::try_from_str_with("((1d6 - 3) ^ 0.5)", &registry).unwrap().analyze(0);
+/// assert_eq!((analysis.min, analysis.max), (f64::NEG_INFINITY, f64::INFINITY));
+/// ```
+///
+/// Equality and hashing are structural, with numbers compared bit by bit and source spans ignored.
+/// Compare [`Evaluate::canonicalize`]d expressions to find the same formula written differently.
+#[derive(Clone, Debug)]
 pub struct Evaluate {
     node: Node,
+    span: Option<Span>,
+}
+
+impl PartialEq for Evaluate {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl Eq for Evaluate {}
+
+impl Hash for Evaluate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.node.hash(state);
+    }
 }
 
 impl Evaluate {
     pub fn try_from_str(s: &str) -> Result<Self, String> {
-        let operation = operation::operation(s).map_err(|err| err.to_string())?.1;
-        (*operation)
-            .try_into()
-            .map_err(|_| "Cannot parse expression".to_string())
+        Self::try_from_str_with(s, &FunctionRegistry::default())
+    }
+
+    /// Parse an expression which may call the functions in `registry`
+    ///
+    /// Functions defined with `def` shadow registered functions of the same name.
+    /// Calls to unknown functions or with the wrong number of arguments are rejected.
+    pub fn try_from_str_with(s: &str, registry: &FunctionRegistry) -> Result<Self, String> {
+        Self::try_from_str_with_variables(s, &[], registry)
+    }
+
+    /// Parse an expression which may use free `variables` besides `x`
+    ///
+    /// Free variables must be given values with [`Evaluate::bind`] before evaluation.
+    ///
+    /// # Errors
+    ///
+    /// If `s` does not parse, or a name in `variables` could not be written in an expression
+    pub fn try_from_str_with_variables(
+        s: &str,
+        variables: &[&str],
+        registry: &FunctionRegistry,
+    ) -> Result<Self, String> {
+        if let Some(variable) = variables.iter().find(|variable| !atom::is_name(variable)) {
+            return Err(format!("Invalid variable name: {variable}"));
+        }
+        let (rest, operation) = operation::program(s).map_err(|err| err.to_string())?;
+        if !rest.trim().is_empty() {
+            return Err(format!("Unexpected input: {}", rest.trim()));
+        }
+        let mut evaluate: Self = (*operation).into();
+        evaluate.locate(s.len());
+        let mut names = variables
+            .iter()
+            .map(|variable| scope::Name::Value(variable.to_string()))
+            .collect();
+        scope::resolve(&mut evaluate, &mut names, registry)?;
+        Ok(evaluate)
+    }
+
+    /// Substitute values for variables and fold the arithmetic which became constant
+    ///
+    /// # Return
+    ///
+    /// The residual expression in the remaining variables
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::Evaluate as Super;
+    /// use flp_math::evaluate::expression::{Evaluate, FunctionRegistry};
+    /// let curve = Evaluate::try_from_str_with_variables(
+    ///     "((x * (difficulty + 1)) + (region * 10))",
+    ///     &["difficulty", "region"],
+    ///     &FunctionRegistry::default(),
+    /// )
+    /// .unwrap();
+    /// let partial = curve.bind(&[("difficulty", 1.0)]);
+    /// assert_eq!(partial.to_string(), "((x * 2) + (region * 10))");
+    /// assert_eq!(partial.free_variables(), vec!["region", "x"]);
+    /// let zone = curve.bind(&[("difficulty", 2.0), ("region", 3.0)]);
+    /// assert_eq!(zone.to_string(), "((x * 3) + 30)");
+    /// assert_eq!(Super::<u8, u16>::evaluate(&zone, 10), 60);
+    ///
+    /// assert_eq!(
+    ///     Evaluate::try_from_str_with_variables("(x * nan)", &["nan"], &FunctionRegistry::default())
+    ///         .err(),
+    ///     Some(String::from("Invalid variable name: nan"))
+    /// );
+    /// ```
+    pub fn bind(&self, values: &[(&str, f64)]) -> Self {
+        let mut values = values
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect();
+        partial::bind(self, &mut values)
+    }
+
+    /// Names of the variables this expression uses without binding them, in order
+    pub fn free_variables(&self) -> Vec<String> {
+        let mut free = BTreeSet::new();
+        partial::free_variables(self, &mut Vec::new(), &mut free);
+        free.into_iter().collect()
+    }
+
+    /// Source span of this expression, if it was parsed from a string
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Turn spans counted back from the end of a source of length `len` into offsets from its start
+    fn locate(&mut self, len: usize) {
+        if let Some(span) = self.span.as_mut() {
+            span.start = len - span.start;
+            span.end = len - span.end;
+        }
+        for child in self.children_mut() {
+            child.locate(len);
+        }
+    }
+
+    fn children(&self) -> Vec<&Self> {
+        match &self.node {
+            Node::Add(node)
+            | Node::Sub(node)
+            | Node::Mul(node)
+            | Node::Div(node)
+            | Node::Mod(node)
+            | Node::Pow(node)
+            | Node::Log(node)
+            | Node::Rand(node)
+            | Node::RandInt(node) => vec![node.left.as_ref(), node.right.as_ref()],
+            Node::Number(_) | Node::Variable(_) | Node::Dice(_) => Vec::new(),
+            Node::Let(node) => vec![node.value.as_ref(), node.body.as_ref()],
+            Node::Def(node) => vec![node.value.as_ref(), node.body.as_ref()],
+            Node::Call(node) => node.args.iter().collect(),
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match &mut self.node {
+            Node::Add(node)
+            | Node::Sub(node)
+            | Node::Mul(node)
+            | Node::Div(node)
+            | Node::Mod(node)
+            | Node::Pow(node)
+            | Node::Log(node)
+            | Node::Rand(node)
+            | Node::RandInt(node) => vec![node.left.as_mut(), node.right.as_mut()],
+            Node::Number(_) | Node::Variable(_) | Node::Dice(_) => Vec::new(),
+            Node::Let(node) => vec![node.value.as_mut(), node.body.as_mut()],
+            Node::Def(node) => vec![node.value.as_mut(), node.body.as_mut()],
+            Node::Call(node) => node.args.iter_mut().collect(),
+        }
+    }
+
+    /// Evaluate, recording the value of every sub-expression
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::expression::Evaluate;
+    /// let evaluate = Evaluate::try_from_str("((x * 2) + (x log 2))").unwrap();
+    /// let explanation = evaluate.explain(4);
+    /// assert_eq!(explanation.value, 10.0);
+    /// assert_eq!(
+    ///     explanation.to_string(),
+    ///     "((x * 2) + (x log 2)) = 10
+    ///   (x * 2) = 8
+    ///     x = 4
+    ///     2 = 2
+    ///   (x log 2) = 2
+    ///     x = 4
+    ///     2 = 2
+    /// "
+    /// );
+    /// ```
+    pub fn explain<X>(&self, x: X) -> Explanation
+    where
+        X: ToPrimitive,
+    {
+        let trace = RefCell::new(explain::Trace::default());
+        evaluate_recursive(
+            self,
+            &Scope::Root {
+                value: x.to_f64().expect("Cannot convert X to f64"),
+                rng: None,
+                diagnostics: None,
+                trace: Some(&trace),
+            },
+        );
+        trace.into_inner().finish()
+    }
+
+    /// Evaluate, reporting the sub-expressions which went wrong
+    ///
+    /// # Return
+    ///
+    /// The value with any warnings, or the first error
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::expression::{DiagnosticKind, Evaluate, Span};
+    /// let evaluate = Evaluate::try_from_str("((x * 2) + (100 / (x - 73)))").unwrap();
+    /// assert_eq!(evaluate.try_evaluate(72).unwrap().value, 44.0);
+    /// let error = evaluate.try_evaluate(73).unwrap_err();
+    /// assert_eq!(error.kind, DiagnosticKind::DivisionByZero);
+    /// assert_eq!(error.span, Some(Span { start: 11, end: 27 }));
+    /// assert_eq!(error.expression, "(100 / (x - 73))");
+    /// ```
+    pub fn try_evaluate<X>(&self, x: X) -> Result<Evaluation, Diagnostic>
+    where
+        X: ToPrimitive,
+    {
+        let diagnostics = RefCell::new(Vec::new());
+        let value = evaluate_recursive(
+            self,
+            &Scope::Root {
+                value: x.to_f64().expect("Cannot convert X to f64"),
+                rng: None,
+                diagnostics: Some(&diagnostics),
+                trace: None,
+            },
+        );
+        let (errors, warnings): (Vec<Diagnostic>, Vec<Diagnostic>) = diagnostics
+            .into_inner()
+            .into_iter()
+            .partition(|diagnostic| diagnostic.kind.is_error());
+        match errors.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(Evaluation { value, warnings }),
+        }
+    }
+
+    /// Evaluate with random terms rolled by `rng`
+    ///
+    /// A random term whose bounds are not finite or not increasing takes its lower bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::expression::{Evaluate, FunctionRegistry};
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// let mut registry = FunctionRegistry::new();
+    /// registry.allow_random();
+    /// let evaluate = Evaluate::try_from_str_with("rand(0, (1 / x))", &registry).unwrap();
+    /// let value: f64 = evaluate.evaluate_with_rng(0, &mut StdRng::seed_from_u64(7));
+    /// assert_eq!(value, 0.0);
+    /// ```
+    pub fn evaluate_with_rng<X, Y, R>(&self, x: X, rng: &mut R) -> Y
+    where
+        X: ToPrimitive,
+        Y: FromPrimitive,
+        R: RngCore,
+    {
+        let rng = RefCell::new(rng);
+        Y::from_f64(evaluate_recursive(
+            self,
+            &Scope::Root {
+                value: x.to_f64().expect("Cannot convert X to f64"),
+                rng: Some(&rng),
+                diagnostics: None,
+                trace: None,
+            },
+        ))
+        .expect("Cannot convert f64 to Y")
+    }
+
+    /// Closed form of the sum of this expression over `1..=x`
+    ///
+    /// Supports sums of polynomials in `x` and geometric terms `a * b ^ x`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::Evaluate as Super;
+    /// use flp_math::evaluate::expression::Evaluate;
+    /// let evaluate = Evaluate::try_from_str("((x ^ 2) + (3 * (2 ^ x)))").unwrap();
+    /// let sum = evaluate.prefix_sum().unwrap();
+    /// let expected: u32 = (1..=10u32).map(|x| x * x + 3 * 2u32.pow(x)).sum();
+    /// assert_eq!(Super::<u32, u32>::evaluate(&sum, 10), expected);
+    /// assert!(Evaluate::try_from_str("(x log 2)").unwrap().prefix_sum().is_err());
+    /// ```
+    pub fn prefix_sum(&self) -> Result<Self, String> {
+        summation::prefix_sum(self)
+    }
+
+    /// Canonical form of this expression
+    ///
+    /// Chains of `+` and `*` are flattened and their operands sorted, and `-0` becomes `0`,
+    /// so formulas which only differ in those respects become equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    /// use flp_math::evaluate::expression::Evaluate;
+    /// let curves: HashSet<Evaluate> = ["(x + 2)", "(2 + x)", "((x * 3) + (1 + x))", "((1 + x) + (3 * x))"]
+    ///     .into_iter()
+    ///     .map(|s| Evaluate::try_from_str(s).unwrap().canonicalize())
+    ///     .collect();
+    /// assert_eq!(curves.len(), 2);
+    /// assert_ne!(
+    ///     Evaluate::try_from_str("(x - 2)").unwrap().canonicalize(),
+    ///     Evaluate::try_from_str("(2 - x)").unwrap().canonicalize()
+    /// );
+    /// ```
+    pub fn canonicalize(&self) -> Self {
+        canonical::canonicalize(self)
+    }
+
+    fn binary(node: fn(BinaryOperation) -> Node, left: Self, right: Self) -> Self {
+        node(BinaryOperation::new(left, right)).into()
+    }
+
+    /// Range and expected value at `x` without rolling random terms
+    pub fn analyze<X>(&self, x: X) -> Analysis
+    where
+        X: ToPrimitive,
+    {
+        analysis::analyze_recursive(
+            self,
+            &Scope::root(Analysis::exact(
+                x.to_f64().expect("Cannot convert X to f64"),
+            )),
+        )
+    }
+
+    /// Suspicious constructs when `x` ranges over `min..=max`, without rejecting the curve
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::expression::{Evaluate, LintKind, Severity};
+    /// let curve = Evaluate::try_from_str("((x % 0) + (100 / (x - 5)))").unwrap();
+    /// let lints = curve.lint(1, 10);
+    /// assert_eq!(lints[0].kind, LintKind::ModuloByZero);
+    /// assert_eq!(lints[0].severity(), Severity::Error);
+    /// assert_eq!(lints[0].to_string(), "error: modulo by zero in (x % 0) at 1..8");
+    /// assert_eq!(lints[1].kind, LintKind::PossibleDivisionByZero);
+    /// assert_eq!(lints[1].severity(), Severity::Warning);
+    /// let curve = Evaluate::try_from_str("(100 / ((x - 5) ^ 2))").unwrap();
+    /// let lints = curve.lint(1, 10);
+    /// assert_eq!(lints.len(), 1);
+    /// assert_eq!(lints[0].kind, LintKind::PossibleDivisionByZero);
+    ///
+    /// let kinds = |s: &str| -> Vec<LintKind> {
+    ///     let curve = Evaluate::try_from_str(s).unwrap();
+    ///     curve.lint(1, 99).into_iter().map(|lint| lint.kind).collect()
+    /// };
+    /// assert_eq!(kinds("(x log 1)"), vec![LintKind::InvalidLogBase]);
+    /// assert_eq!(kinds("(x ^ x)"), vec![LintKind::VariableBaseAndExponent]);
+    /// assert_eq!(kinds("((x * 0) + 5)"), vec![LintKind::ConstantCurve]);
+    /// assert_eq!(kinds("def f(a, b) = (a / b); f(x, 2)"), vec![]);
+    /// assert_eq!(kinds("((x ^ 2) * 100)"), vec![]);
+    ///
+    /// let curve =
+    ///     Evaluate::try_from_str_with_variables("(x * difficulty)", &["difficulty"], &Default::default())
+    ///         .unwrap();
+    /// assert_eq!(curve.lint(1, 99), vec![]);
+    /// ```
+    pub fn lint<X>(&self, min: X, max: X) -> Vec<Lint>
+    where
+        X: ToPrimitive,
+    {
+        let min = min.to_f64().expect("Cannot convert X to f64");
+        let max = max.to_f64().expect("Cannot convert X to f64");
+        let scope = Scope::root(Analysis {
+            min,
+            max,
+            expected: (min + max) / 2.0,
+        });
+        let (input, free): (Vec<String>, Vec<String>) = self
+            .free_variables()
+            .into_iter()
+            .partition(|name| name == INPUT);
+        scope.with_values(&free, lint::PARAMETER, |scope| {
+            let mut lints = Vec::new();
+            let analysis = analysis::analyze_recursive(self, scope);
+            if min < max && ((analysis.is_exact() && analysis.min.is_finite()) || input.is_empty())
+            {
+                lints.push(Lint {
+                    kind: LintKind::ConstantCurve,
+                    span: self.span,
+                    expression: self.to_string(),
+                });
+            }
+            lint::lint_recursive(self, scope, &mut lints);
+            lints
+        })
+    }
+}
+
+impl From<Node> for Evaluate {
+    fn from(node: Node) -> Self {
+        Self { node, span: None }
     }
 }
 
 impl From<f64> for Evaluate {
     fn from(value: f64) -> Self {
-        Self {
-            node: Node::Number(value),
-        }
+        Node::Number(value).into()
     }
 }
 
 impl From<Variable> for Evaluate {
-    fn from(_: Variable) -> Self {
-        Self {
-            node: Node::Variable,
-        }
+    fn from(value: Variable) -> Self {
+        Node::Variable(value.0).into()
     }
 }
 
@@ -91,285 +664,394 @@ impl From<Operation> for Evaluate {
         match value {
             N(value) => value.into(),
             V(value) => value.into(),
-            NAddN { left, right } => Self {
-                node: Node::Add(BinaryOperation::new(left.into(), right.into())),
-            },
-            NAddV { left, right } => Self {
-                node: Node::Add(BinaryOperation::new(left.into(), right.into())),
-            },
-            NAddO { left, right } => Self {
-                node: Node::Add(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            VAddN { left, right } => Self {
-                node: Node::Add(BinaryOperation::new(left.into(), right.into())),
-            },
-            VAddV { left, right } => Self {
-                node: Node::Add(BinaryOperation::new(left.into(), right.into())),
-            },
-            VAddO { left, right } => Self {
-                node: Node::Add(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            OAddN { left, right } => Self {
-                node: Node::Add(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OAddV { left, right } => Self {
-                node: Node::Add(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OAddO { left, right } => Self {
-                node: Node::Add(BinaryOperation::new((*left).into(), (*right).into())),
-            },
-            NSubN { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new(left.into(), right.into())),
-            },
-            NSubV { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new(left.into(), right.into())),
-            },
-            NSubO { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            VSubN { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new(left.into(), right.into())),
-            },
-            VSubV { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new(left.into(), right.into())),
-            },
-            VSubO { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            OSubN { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OSubV { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OSubO { left, right } => Self {
-                node: Node::Sub(BinaryOperation::new((*left).into(), (*right).into())),
-            },
-            NMulN { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new(left.into(), right.into())),
-            },
-            NMulV { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new(left.into(), right.into())),
-            },
-            NMulO { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            VMulN { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new(left.into(), right.into())),
-            },
-            VMulV { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new(left.into(), right.into())),
-            },
-            VMulO { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            OMulN { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OMulV { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OMulO { left, right } => Self {
-                node: Node::Mul(BinaryOperation::new((*left).into(), (*right).into())),
-            },
-            NDivN { left, right } => Self {
-                node: Node::Div(BinaryOperation::new(left.into(), right.into())),
-            },
-            NDivV { left, right } => Self {
-                node: Node::Div(BinaryOperation::new(left.into(), right.into())),
-            },
-            NDivO { left, right } => Self {
-                node: Node::Div(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            VDivN { left, right } => Self {
-                node: Node::Div(BinaryOperation::new(left.into(), right.into())),
-            },
-            VDivV { left, right } => Self {
-                node: Node::Div(BinaryOperation::new(left.into(), right.into())),
-            },
-            VDivO { left, right } => Self {
-                node: Node::Div(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            ODivN { left, right } => Self {
-                node: Node::Div(BinaryOperation::new((*left).into(), right.into())),
-            },
-            ODivV { left, right } => Self {
-                node: Node::Div(BinaryOperation::new((*left).into(), right.into())),
-            },
-            ODivO { left, right } => Self {
-                node: Node::Div(BinaryOperation::new((*left).into(), (*right).into())),
-            },
-            NModN { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new(left.into(), right.into())),
-            },
-            NModV { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new(left.into(), right.into())),
-            },
-            NModO { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            VModN { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new(left.into(), right.into())),
-            },
-            VModV { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new(left.into(), right.into())),
-            },
-            VModO { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            OModN { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OModV { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OModO { left, right } => Self {
-                node: Node::Mod(BinaryOperation::new((*left).into(), (*right).into())),
-            },
-            NPowN { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new(left.into(), right.into())),
-            },
-            NPowV { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new(left.into(), right.into())),
-            },
-            NPowO { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            VPowN { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new(left.into(), right.into())),
-            },
-            VPowV { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new(left.into(), right.into())),
-            },
-            VPowO { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            OPowN { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OPowV { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OPowO { left, right } => Self {
-                node: Node::Pow(BinaryOperation::new((*left).into(), (*right).into())),
-            },
-            NLogN { left, right } => Self {
-                node: Node::Log(BinaryOperation::new(left.into(), right.into())),
-            },
-            NLogV { left, right } => Self {
-                node: Node::Log(BinaryOperation::new(left.into(), right.into())),
-            },
-            NLogO { left, right } => Self {
-                node: Node::Log(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            VLogN { left, right } => Self {
-                node: Node::Log(BinaryOperation::new(left.into(), right.into())),
-            },
-            VLogV { left, right } => Self {
-                node: Node::Log(BinaryOperation::new(left.into(), right.into())),
-            },
-            VLogO { left, right } => Self {
-                node: Node::Log(BinaryOperation::new(left.into(), (*right).into())),
-            },
-            OLogN { left, right } => Self {
-                node: Node::Log(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OLogV { left, right } => Self {
-                node: Node::Log(BinaryOperation::new((*left).into(), right.into())),
-            },
-            OLogO { left, right } => Self {
-                node: Node::Log(BinaryOperation::new((*left).into(), (*right).into())),
+            Spanned {
+                operation,
+                start,
+                end,
+            } => Self {
+                span: Some(Span { start, end }),
+                ..(*operation).into()
             },
+            NAddN { left, right } => {
+                Node::Add(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NAddV { left, right } => {
+                Node::Add(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NAddO { left, right } => {
+                Node::Add(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            VAddN { left, right } => {
+                Node::Add(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VAddV { left, right } => {
+                Node::Add(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VAddO { left, right } => {
+                Node::Add(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            OAddN { left, right } => {
+                Node::Add(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OAddV { left, right } => {
+                Node::Add(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OAddO { left, right } => {
+                Node::Add(BinaryOperation::new((*left).into(), (*right).into())).into()
+            }
+            NSubN { left, right } => {
+                Node::Sub(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NSubV { left, right } => {
+                Node::Sub(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NSubO { left, right } => {
+                Node::Sub(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            VSubN { left, right } => {
+                Node::Sub(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VSubV { left, right } => {
+                Node::Sub(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VSubO { left, right } => {
+                Node::Sub(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            OSubN { left, right } => {
+                Node::Sub(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OSubV { left, right } => {
+                Node::Sub(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OSubO { left, right } => {
+                Node::Sub(BinaryOperation::new((*left).into(), (*right).into())).into()
+            }
+            NMulN { left, right } => {
+                Node::Mul(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NMulV { left, right } => {
+                Node::Mul(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NMulO { left, right } => {
+                Node::Mul(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            VMulN { left, right } => {
+                Node::Mul(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VMulV { left, right } => {
+                Node::Mul(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VMulO { left, right } => {
+                Node::Mul(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            OMulN { left, right } => {
+                Node::Mul(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OMulV { left, right } => {
+                Node::Mul(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OMulO { left, right } => {
+                Node::Mul(BinaryOperation::new((*left).into(), (*right).into())).into()
+            }
+            NDivN { left, right } => {
+                Node::Div(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NDivV { left, right } => {
+                Node::Div(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NDivO { left, right } => {
+                Node::Div(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            VDivN { left, right } => {
+                Node::Div(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VDivV { left, right } => {
+                Node::Div(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VDivO { left, right } => {
+                Node::Div(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            ODivN { left, right } => {
+                Node::Div(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            ODivV { left, right } => {
+                Node::Div(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            ODivO { left, right } => {
+                Node::Div(BinaryOperation::new((*left).into(), (*right).into())).into()
+            }
+            NModN { left, right } => {
+                Node::Mod(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NModV { left, right } => {
+                Node::Mod(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NModO { left, right } => {
+                Node::Mod(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            VModN { left, right } => {
+                Node::Mod(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VModV { left, right } => {
+                Node::Mod(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VModO { left, right } => {
+                Node::Mod(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            OModN { left, right } => {
+                Node::Mod(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OModV { left, right } => {
+                Node::Mod(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OModO { left, right } => {
+                Node::Mod(BinaryOperation::new((*left).into(), (*right).into())).into()
+            }
+            NPowN { left, right } => {
+                Node::Pow(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NPowV { left, right } => {
+                Node::Pow(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NPowO { left, right } => {
+                Node::Pow(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            VPowN { left, right } => {
+                Node::Pow(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VPowV { left, right } => {
+                Node::Pow(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VPowO { left, right } => {
+                Node::Pow(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            OPowN { left, right } => {
+                Node::Pow(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OPowV { left, right } => {
+                Node::Pow(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OPowO { left, right } => {
+                Node::Pow(BinaryOperation::new((*left).into(), (*right).into())).into()
+            }
+            NLogN { left, right } => {
+                Node::Log(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NLogV { left, right } => {
+                Node::Log(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            NLogO { left, right } => {
+                Node::Log(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            VLogN { left, right } => {
+                Node::Log(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VLogV { left, right } => {
+                Node::Log(BinaryOperation::new(left.into(), right.into())).into()
+            }
+            VLogO { left, right } => {
+                Node::Log(BinaryOperation::new(left.into(), (*right).into())).into()
+            }
+            OLogN { left, right } => {
+                Node::Log(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OLogV { left, right } => {
+                Node::Log(BinaryOperation::new((*left).into(), right.into())).into()
+            }
+            OLogO { left, right } => {
+                Node::Log(BinaryOperation::new((*left).into(), (*right).into())).into()
+            }
+            Call { name, args } => Node::Call(FunctionCall {
+                name,
+                args: args.into_iter().map(Into::into).collect(),
+                host: None,
+            })
+            .into(),
+            Let { name, value, body } => Node::Let(LetBinding {
+                name,
+                value: Box::new((*value).into()),
+                body: Box::new((*body).into()),
+            })
+            .into(),
+            Def {
+                name,
+                params,
+                value,
+                body,
+            } => Node::Def(FunctionDefinition {
+                name,
+                params,
+                value: Box::new((*value).into()),
+                body: Box::new((*body).into()),
+            })
+            .into(),
+            Rand { low, high } => {
+                Node::Rand(BinaryOperation::new((*low).into(), (*high).into())).into()
+            }
+            RandInt { low, high } => {
+                Node::RandInt(BinaryOperation::new((*low).into(), (*high).into())).into()
+            }
+            Dice { count, sides } => Node::Dice(DiceRoll { count, sides }).into(),
         }
     }
 }
 
-impl ToString for Evaluate {
-    fn to_string(&self) -> String {
+impl Display for Evaluate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.node {
-            Node::Add(node) => {
-                format!(
-                    "({} + {})",
-                    node.left.as_ref().to_string(),
-                    node.right.as_ref().to_string()
-                )
-            }
-            Node::Sub(node) => {
-                format!(
-                    "({} - {})",
-                    node.left.as_ref().to_string(),
-                    node.right.as_ref().to_string()
-                )
-            }
-            Node::Mul(node) => {
-                format!(
-                    "({} * {})",
-                    node.left.as_ref().to_string(),
-                    node.right.as_ref().to_string()
-                )
-            }
-            Node::Div(node) => {
-                format!(
-                    "({} / {})",
-                    node.left.as_ref().to_string(),
-                    node.right.as_ref().to_string()
-                )
-            }
-            Node::Mod(node) => {
-                format!(
-                    "({} % {})",
-                    node.left.as_ref().to_string(),
-                    node.right.as_ref().to_string()
-                )
-            }
-            Node::Pow(node) => {
-                format!(
-                    "({} ^ {})",
-                    node.left.as_ref().to_string(),
-                    node.right.as_ref().to_string()
-                )
-            }
-            Node::Log(node) => {
-                format!(
-                    "({} log {})",
-                    node.left.as_ref().to_string(),
-                    node.right.as_ref().to_string()
-                )
-            }
-            Node::Number(node) => node.to_string(),
-            Node::Variable => "x".to_string(),
+            Node::Add(node) => write!(f, "({} + {})", node.left, node.right),
+            Node::Sub(node) => write!(f, "({} - {})", node.left, node.right),
+            Node::Mul(node) => write!(f, "({} * {})", node.left, node.right),
+            Node::Div(node) => write!(f, "({} / {})", node.left, node.right),
+            Node::Mod(node) => write!(f, "({} % {})", node.left, node.right),
+            Node::Pow(node) => write!(f, "({} ^ {})", node.left, node.right),
+            Node::Log(node) => write!(f, "({} log {})", node.left, node.right),
+            Node::Number(node) => write!(f, "{node}"),
+            Node::Variable(name) => write!(f, "{name}"),
+            Node::Let(node) => write!(f, "let {} = {}; {}", node.name, node.value, node.body),
+            Node::Def(node) => write!(
+                f,
+                "def {}({}) = {}; {}",
+                node.name,
+                node.params.join(", "),
+                node.value,
+                node.body
+            ),
+            Node::Call(node) => {
+                let args: Vec<String> = node.args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", node.name, args.join(", "))
+            }
+            Node::Rand(node) => write!(f, "rand({}, {})", node.left, node.right),
+            Node::RandInt(node) => write!(f, "randint({}, {})", node.left, node.right),
+            Node::Dice(node) => write!(f, "{}d{}", node.count, node.sides),
         }
     }
 }
 
-fn evaluate_recursive(variable: f64, evaluate: &Evaluate) -> f64 {
+fn evaluate_recursive(evaluate: &Evaluate, scope: &Scope) -> f64 {
+    match scope.trace() {
+        Some(trace) => {
+            trace.borrow_mut().enter();
+            let value = evaluate_node(evaluate, scope);
+            trace.borrow_mut().exit(evaluate, value);
+            value
+        }
+        None => evaluate_node(evaluate, scope),
+    }
+}
+
+fn evaluate_node(evaluate: &Evaluate, scope: &Scope) -> f64 {
     match &evaluate.node {
-        Node::Add(node) => {
-            evaluate_recursive(variable, node.left.as_ref())
-                + evaluate_recursive(variable, node.right.as_ref())
+        Node::Add(node)
+        | Node::Sub(node)
+        | Node::Mul(node)
+        | Node::Div(node)
+        | Node::Mod(node)
+        | Node::Pow(node)
+        | Node::Log(node) => {
+            let left = evaluate_recursive(node.left.as_ref(), scope);
+            let right = evaluate_recursive(node.right.as_ref(), scope);
+            let value = evaluate.node.apply(left, right);
+            if let Some(diagnostics) = scope.diagnostics() {
+                diagnostic::check(evaluate, &[left, right], value, diagnostics);
+            }
+            value
         }
-        Node::Sub(node) => {
-            evaluate_recursive(variable, node.left.as_ref())
-                - evaluate_recursive(variable, node.right.as_ref())
+        Node::Number(node) => *node,
+        Node::Variable(name) => scope.value(name).expect("Unbound variable"),
+        Node::Let(node) => {
+            let value = evaluate_recursive(node.value.as_ref(), scope);
+            evaluate_recursive(
+                node.body.as_ref(),
+                &Scope::Value {
+                    parent: scope,
+                    name: &node.name,
+                    value,
+                },
+            )
         }
-        Node::Mul(node) => {
-            evaluate_recursive(variable, node.left.as_ref())
-                * evaluate_recursive(variable, node.right.as_ref())
+        Node::Def(node) => evaluate_recursive(
+            node.body.as_ref(),
+            &Scope::Function {
+                parent: scope,
+                definition: node,
+            },
+        ),
+        Node::Call(node) => {
+            let args: Vec<f64> = node
+                .args
+                .iter()
+                .map(|arg| evaluate_recursive(arg, scope))
+                .collect();
+            match &node.host {
+                Some(host) => {
+                    let value = host.call(&args);
+                    if let Some(diagnostics) = scope.diagnostics() {
+                        diagnostic::check(evaluate, &args, value, diagnostics);
+                    }
+                    value
+                }
+                None => {
+                    let (definition, definition_scope) =
+                        scope.function(&node.name).expect("Unknown function");
+                    call_recursive(definition, &definition.params, &args, definition_scope)
+                }
+            }
         }
-        Node::Div(node) => {
-            evaluate_recursive(variable, node.left.as_ref())
-                / evaluate_recursive(variable, node.right.as_ref())
+        Node::Rand(node) => {
+            let low = evaluate_recursive(node.left.as_ref(), scope);
+            let high = evaluate_recursive(node.right.as_ref(), scope);
+            let mut rng = roll(scope);
+            if low < high && (high - low).is_finite() {
+                rng.gen_range(low..high)
+            } else {
+                low
+            }
         }
-        Node::Mod(node) => {
-            evaluate_recursive(variable, node.left.as_ref())
-                % evaluate_recursive(variable, node.right.as_ref())
+        Node::RandInt(node) => {
+            let low = evaluate_recursive(node.left.as_ref(), scope).ceil();
+            let high = evaluate_recursive(node.right.as_ref(), scope).floor();
+            let mut rng = roll(scope);
+            if low < high && (high - low).is_finite() {
+                rng.gen_range(low as i64..=high as i64) as f64
+            } else {
+                low
+            }
         }
-        Node::Pow(node) => evaluate_recursive(variable, node.left.as_ref())
-            .powf(evaluate_recursive(variable, node.right.as_ref())),
-        Node::Log(node) => evaluate_recursive(variable, node.left.as_ref())
-            .log(evaluate_recursive(variable, node.right.as_ref())),
-        Node::Number(node) => *node,
-        Node::Variable => variable,
+        Node::Dice(node) => {
+            let mut rng = roll(scope);
+            (0..node.count)
+                .map(|_| rng.gen_range(1..=node.sides as u64))
+                .sum::<u64>() as f64
+        }
+    }
+}
+
+fn roll<'a>(scope: &Scope<'a>) -> RefMut<'a, dyn RngCore + 'a> {
+    scope
+        .rng()
+        .expect("Cannot roll without a random number generator")
+        .borrow_rng()
+}
+
+fn call_recursive(
+    definition: &FunctionDefinition,
+    params: &[String],
+    args: &[f64],
+    scope: &Scope,
+) -> f64 {
+    match (params.split_first(), args.split_first()) {
+        (Some((name, params)), Some((value, args))) => call_recursive(
+            definition,
+            params,
+            args,
+            &Scope::Value {
+                parent: scope,
+                name,
+                value: *value,
+            },
+        ),
+        _ => evaluate_recursive(definition.value.as_ref(), scope),
     }
 }
 
@@ -380,16 +1062,16 @@ where
 {
     fn evaluate(&self, x: X) -> Y {
         Y::from_f64(evaluate_recursive(
-            x.to_f64().expect("Cannot convert X to f64"),
             self,
+            &Scope::root(x.to_f64().expect("Cannot convert X to f64")),
         ))
         .expect("Cannot convert f64 to Y")
     }
 }
 
-struct EvaluateVisitor;
+struct EvaluateVisitor<'a>(&'a FunctionRegistry);
 
-impl<'de> Visitor<'de> for EvaluateVisitor {
+impl<'de> Visitor<'de> for EvaluateVisitor<'_> {
     type Value = Evaluate;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -400,23 +1082,91 @@ impl<'de> Visitor<'de> for EvaluateVisitor {
     where
         E: de::Error,
     {
-        Evaluate::try_from_str(v).map_err(|err| de::Error::custom(err))
+        Evaluate::try_from_str_with(v, self.0).map_err(|err| de::Error::custom(err))
     }
 
     fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        Evaluate::try_from_str(v.as_str()).map_err(|err| de::Error::custom(err))
+        Evaluate::try_from_str_with(v.as_str(), self.0).map_err(|err| de::Error::custom(err))
     }
 }
 
+/// Expressions deserialize from their source string in any self-describing format.
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::expression::Evaluate;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct Config {
+///     curve: Evaluate,
+/// }
+///
+/// let config: Config = serde_json::from_str(r#"{"curve":"((x ^ 2) * 100)"}"#).unwrap();
+/// assert_eq!(serde_json::to_string(&config).unwrap(), r#"{"curve":"((x ^ 2) * 100)"}"#);
+///
+/// let config: Config = toml::from_str(r#"curve = "let a = (x + 1); (a * a)""#).unwrap();
+/// assert_eq!(toml::to_string(&config).unwrap(), "curve = \"let a = (x + 1); (a * a)\"\n");
+///
+/// let config: Config = ron::from_str(r#"(curve: "x")"#).unwrap();
+/// assert_eq!(ron::to_string(&config).unwrap(), r#"(curve:"x")"#);
+///
+/// assert!(serde_json::from_str::<Config>(r#"{"curve":"(y + 1)"}"#).is_err());
+/// ```
+///
+/// Only the default functions are known here, so expressions calling host functions must be
+/// deserialized with their [`FunctionRegistry`] as a [`DeserializeSeed`].
+///
+/// A [`Growth`](crate::growth::Growth) with an expression curve loads from config:
+///
+/// ```
+/// use flp_math::evaluate::expression::Evaluate;
+/// use flp_math::growth::Growth;
+///
+/// let mut growth: Growth<u8, u32, Evaluate> = serde_json::from_str(
+///     r#"{"evaluation":"((x ^ 2) * 100)","progress":{"current":0,"max":100}}"#,
+/// )
+/// .unwrap();
+/// growth.apply_level(3);
+/// assert_eq!(*growth.progress().max(), 900);
+/// ```
 impl<'de> Deserialize<'de> for Evaluate {
     fn deserialize<D>(deserializer: D) -> Result<Evaluate, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_i32(EvaluateVisitor)
+        deserializer.deserialize_str(EvaluateVisitor(&FunctionRegistry::default()))
+    }
+}
+
+/// Deserialize an expression which may call the functions in the registry
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::expression::{Evaluate, FunctionRegistry};
+/// use serde::de::DeserializeSeed;
+///
+/// let mut registry = FunctionRegistry::new();
+/// registry.register("tier", 1, |args| (args[0] / 10.0).floor()).unwrap();
+/// let evaluate = Evaluate::try_from_str_with("(tier(x) * 100)", &registry).unwrap();
+/// let json = serde_json::to_string(&evaluate).unwrap();
+/// assert!(serde_json::from_str::<Evaluate>(&json).is_err());
+/// let mut deserializer = serde_json::Deserializer::from_str(&json);
+/// assert_eq!((&registry).deserialize(&mut deserializer).unwrap(), evaluate);
+/// ```
+impl<'de> DeserializeSeed<'de> for &FunctionRegistry {
+    type Value = Evaluate;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Evaluate, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(EvaluateVisitor(self))
     }
 }
 
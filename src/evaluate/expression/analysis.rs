@@ -0,0 +1,256 @@
+use super::{scope::Scope, Evaluate, FunctionDefinition, Node};
+
+/// Range and expected value of an expression
+///
+/// Operands are treated as independent, so the expected value of a nonlinear
+/// operation is approximated by applying it to the expected operands, and host
+/// functions are assumed to be monotonic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Analysis {
+    pub min: f64,
+    pub max: f64,
+    pub expected: f64,
+}
+
+impl Analysis {
+    pub fn exact(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+            expected: value,
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.min == self.max
+    }
+
+    fn from_candidates(candidates: &[f64], expected: f64) -> Self {
+        Self {
+            min: candidates.iter().copied().fold(f64::INFINITY, f64::min),
+            max: candidates.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            expected,
+        }
+    }
+}
+
+fn corners(left: Analysis, right: Analysis, f: impl Fn(f64, f64) -> f64) -> Analysis {
+    Analysis::from_candidates(
+        &[
+            f(left.min, right.min),
+            f(left.min, right.max),
+            f(left.max, right.min),
+            f(left.max, right.max),
+        ],
+        f(left.expected, right.expected),
+    )
+}
+
+fn modulo(left: Analysis, right: Analysis) -> Analysis {
+    if left.is_exact() && right.is_exact() {
+        return Analysis::exact(left.min % right.min);
+    }
+    let bound = right.min.abs().max(right.max.abs());
+    Analysis {
+        min: if left.min >= 0.0 {
+            0.0
+        } else {
+            left.min.max(-bound)
+        },
+        max: if left.max <= 0.0 {
+            0.0
+        } else {
+            left.max.min(bound)
+        },
+        expected: left.expected % right.expected,
+    }
+}
+
+const UNBOUNDED: Analysis = Analysis {
+    min: f64::NEG_INFINITY,
+    max: f64::INFINITY,
+    expected: f64::NAN,
+};
+
+fn power(base: Analysis, exponent: Analysis) -> Analysis {
+    let expected = base.expected.powf(exponent.expected);
+    let range = corners(base, exponent, f64::powf);
+    let integer = exponent.is_exact() && exponent.min.fract() == 0.0;
+    if base.min < 0.0 && !integer && !(base.is_exact() && exponent.is_exact()) {
+        // Negative bases have no real fractional powers, and varying exponents alternate signs
+        return Analysis {
+            expected,
+            ..UNBOUNDED
+        };
+    }
+    if !(base.min < 0.0 && base.max > 0.0) || exponent.min == 0.0 {
+        return range;
+    }
+    // Integer powers of a base range straddling zero reach 0^e between the corners
+    let even = exponent.min % 2.0 == 0.0;
+    match (exponent.min > 0.0, even) {
+        (true, true) => Analysis { min: 0.0, ..range },
+        (true, false) => range,
+        (false, true) => Analysis {
+            max: f64::INFINITY,
+            ..range
+        },
+        (false, false) => Analysis {
+            expected,
+            ..UNBOUNDED
+        },
+    }
+}
+
+fn logarithm(value: Analysis, base: Analysis) -> Analysis {
+    if !base.is_exact() && (base.min <= 0.0 || (base.min <= 1.0 && base.max >= 1.0)) {
+        return Analysis {
+            expected: value.expected.log(base.expected),
+            ..UNBOUNDED
+        };
+    }
+    // Only the non-negative part of the value has a logarithm, which diverges towards 0
+    let value = if value.min < 0.0 && value.max > 0.0 {
+        Analysis { min: 0.0, ..value }
+    } else {
+        value
+    };
+    corners(value, base, f64::log)
+}
+
+pub fn analyze_recursive(evaluate: &Evaluate, scope: &Scope<Analysis>) -> Analysis {
+    match &evaluate.node {
+        Node::Add(node) => corners(
+            analyze_recursive(node.left.as_ref(), scope),
+            analyze_recursive(node.right.as_ref(), scope),
+            |left, right| left + right,
+        ),
+        Node::Sub(node) => corners(
+            analyze_recursive(node.left.as_ref(), scope),
+            analyze_recursive(node.right.as_ref(), scope),
+            |left, right| left - right,
+        ),
+        Node::Mul(node) => corners(
+            analyze_recursive(node.left.as_ref(), scope),
+            analyze_recursive(node.right.as_ref(), scope),
+            |left, right| left * right,
+        ),
+        Node::Div(node) => {
+            let left = analyze_recursive(node.left.as_ref(), scope);
+            let right = analyze_recursive(node.right.as_ref(), scope);
+            if !right.is_exact() && right.min <= 0.0 && right.max >= 0.0 {
+                Analysis {
+                    min: f64::NEG_INFINITY,
+                    max: f64::INFINITY,
+                    expected: left.expected / right.expected,
+                }
+            } else {
+                corners(left, right, |left, right| left / right)
+            }
+        }
+        Node::Mod(node) => modulo(
+            analyze_recursive(node.left.as_ref(), scope),
+            analyze_recursive(node.right.as_ref(), scope),
+        ),
+        Node::Pow(node) => power(
+            analyze_recursive(node.left.as_ref(), scope),
+            analyze_recursive(node.right.as_ref(), scope),
+        ),
+        Node::Log(node) => logarithm(
+            analyze_recursive(node.left.as_ref(), scope),
+            analyze_recursive(node.right.as_ref(), scope),
+        ),
+        Node::Number(node) => Analysis::exact(*node),
+        Node::Variable(name) => scope.value(name).expect("Unbound variable"),
+        Node::Let(node) => {
+            let value = analyze_recursive(node.value.as_ref(), scope);
+            analyze_recursive(
+                node.body.as_ref(),
+                &Scope::Value {
+                    parent: scope,
+                    name: &node.name,
+                    value,
+                },
+            )
+        }
+        Node::Def(node) => analyze_recursive(
+            node.body.as_ref(),
+            &Scope::Function {
+                parent: scope,
+                definition: node,
+            },
+        ),
+        Node::Call(node) => {
+            let args: Vec<Analysis> = node
+                .args
+                .iter()
+                .map(|arg| analyze_recursive(arg, scope))
+                .collect();
+            match &node.host {
+                Some(host) => {
+                    let min: Vec<f64> = args.iter().map(|arg| arg.min).collect();
+                    let max: Vec<f64> = args.iter().map(|arg| arg.max).collect();
+                    let expected: Vec<f64> = args.iter().map(|arg| arg.expected).collect();
+                    let expected = host.call(&expected);
+                    Analysis::from_candidates(
+                        &[host.call(&min), host.call(&max), expected],
+                        expected,
+                    )
+                }
+                None => {
+                    let (definition, definition_scope) =
+                        scope.function(&node.name).expect("Unknown function");
+                    call_recursive(definition, &definition.params, &args, definition_scope)
+                }
+            }
+        }
+        Node::Rand(node) => {
+            let low = analyze_recursive(node.left.as_ref(), scope);
+            let high = analyze_recursive(node.right.as_ref(), scope);
+            Analysis {
+                min: low.min,
+                max: high.max,
+                expected: (low.expected + high.expected) / 2.0,
+            }
+        }
+        Node::RandInt(node) => {
+            let low = analyze_recursive(node.left.as_ref(), scope);
+            let high = analyze_recursive(node.right.as_ref(), scope);
+            Analysis {
+                min: low.min.ceil(),
+                max: high.max.floor(),
+                expected: (low.expected.ceil() + high.expected.floor()) / 2.0,
+            }
+        }
+        Node::Dice(node) => {
+            let count = node.count as f64;
+            let sides = node.sides as f64;
+            Analysis {
+                min: count,
+                max: count * sides,
+                expected: count * (sides + 1.0) / 2.0,
+            }
+        }
+    }
+}
+
+fn call_recursive(
+    definition: &FunctionDefinition,
+    params: &[String],
+    args: &[Analysis],
+    scope: &Scope<Analysis>,
+) -> Analysis {
+    match (params.split_first(), args.split_first()) {
+        (Some((name, params)), Some((value, args))) => call_recursive(
+            definition,
+            params,
+            args,
+            &Scope::Value {
+                parent: scope,
+                name,
+                value: *value,
+            },
+        ),
+        _ => analyze_recursive(definition.value.as_ref(), scope),
+    }
+}
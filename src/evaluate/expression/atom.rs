@@ -1,9 +1,54 @@
-use nom::{bytes::streaming::tag, combinator::map, number::streaming::double, IResult};
+use nom::{
+    bytes::{complete::take_while, streaming::tag},
+    character::complete::{satisfy, u32},
+    combinator::{map, recognize, verify},
+    number::{complete, streaming::double},
+    sequence::{pair, separated_pair},
+    IResult,
+};
+
+/// Words that cannot be used as names
+pub const RESERVED: [&str; 5] = ["log", "let", "def", "rand", "randint"];
 
 pub fn number(input: &str) -> IResult<&str, f64> {
     double(input)
 }
 
+/// A number which may end the input
+pub fn last_number(input: &str) -> IResult<&str, f64> {
+    complete::double(input)
+}
+
+pub fn identifier(input: &str) -> IResult<&str, String> {
+    map(
+        verify(
+            recognize(pair(
+                satisfy(|c| c.is_ascii_alphabetic() || c == '_'),
+                take_while(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+            )),
+            |name: &str| !RESERVED.contains(&name),
+        ),
+        String::from,
+    )(input)
+}
+
+/// Whether `name` is an identifier which the parser does not read as a reserved word or a number
+pub fn is_name(name: &str) -> bool {
+    matches!(identifier(name), Ok(("", _))) && last_number(name).is_err()
+}
+
+#[derive(Debug)]
+pub struct Variable(pub String);
+
+pub fn variable(input: &str) -> IResult<&str, Variable> {
+    map(identifier, Variable)(input)
+}
+
+/// Dice term `NdM`: roll `N` dice with `M` sides
+pub fn dice(input: &str) -> IResult<&str, (u32, u32)> {
+    separated_pair(u32, tag("d"), verify(u32, |sides| *sides > 0))(input)
+}
+
 macro_rules! operator {
     ($sname:ident, $fname:ident, $symbol:literal) => {
         #[derive(Debug)]
@@ -14,7 +59,6 @@ macro_rules! operator {
     };
 }
 
-operator!(Variable, variable, "x");
 operator!(Add, add, "+");
 operator!(Sub, sub, "-");
 operator!(Mul, mul, "*");
@@ -24,3 +68,10 @@ operator!(Pow, pow, "^");
 operator!(Log, log, "log");
 operator!(PLeft, p_left, "(");
 operator!(PRight, p_right, ")");
+operator!(Comma, comma, ",");
+operator!(Assign, assign, "=");
+operator!(Semicolon, semicolon, ";");
+operator!(Let, let_keyword, "let");
+operator!(Def, def_keyword, "def");
+operator!(Rand, rand_keyword, "rand");
+operator!(RandInt, rand_int_keyword, "randint");
@@ -0,0 +1,92 @@
+use super::{BinaryOperation, Evaluate, FunctionCall, FunctionDefinition, LetBinding, Node};
+
+/// Canonical operands of a chain of the same commutative and associative operation
+fn operands(
+    evaluate: &Evaluate,
+    chain: fn(&Node) -> Option<&BinaryOperation>,
+    result: &mut Vec<Evaluate>,
+) {
+    match chain(&evaluate.node) {
+        Some(node) => {
+            operands(node.left.as_ref(), chain, result);
+            operands(node.right.as_ref(), chain, result);
+        }
+        None => result.push(canonicalize(evaluate)),
+    }
+}
+
+fn sorted_chain(
+    evaluate: &Evaluate,
+    chain: fn(&Node) -> Option<&BinaryOperation>,
+    node: fn(BinaryOperation) -> Node,
+) -> Evaluate {
+    let mut result = Vec::new();
+    operands(evaluate, chain, &mut result);
+    result.sort_by_cached_key(|operand| operand.to_string());
+    result
+        .into_iter()
+        .reduce(|left, right| Evaluate::binary(node, left, right))
+        .expect("Empty chain")
+}
+
+fn binary(node: &BinaryOperation) -> BinaryOperation {
+    BinaryOperation::new(canonicalize(&node.left), canonicalize(&node.right))
+}
+
+pub fn canonicalize(evaluate: &Evaluate) -> Evaluate {
+    let node = match &evaluate.node {
+        Node::Add(_) => {
+            return sorted_chain(
+                evaluate,
+                |node| match node {
+                    Node::Add(node) => Some(node),
+                    _ => None,
+                },
+                Node::Add,
+            )
+        }
+        Node::Mul(_) => {
+            return sorted_chain(
+                evaluate,
+                |node| match node {
+                    Node::Mul(node) => Some(node),
+                    _ => None,
+                },
+                Node::Mul,
+            )
+        }
+        Node::Sub(node) => Node::Sub(binary(node)),
+        Node::Div(node) => Node::Div(binary(node)),
+        Node::Mod(node) => Node::Mod(binary(node)),
+        Node::Pow(node) => Node::Pow(binary(node)),
+        Node::Log(node) => Node::Log(binary(node)),
+        Node::Rand(node) => Node::Rand(binary(node)),
+        Node::RandInt(node) => Node::RandInt(binary(node)),
+        Node::Number(node) => Node::Number(if *node == 0.0 {
+            0.0
+        } else if node.is_nan() {
+            f64::NAN
+        } else {
+            *node
+        }),
+        Node::Variable(name) => Node::Variable(name.clone()),
+        Node::Let(node) => Node::Let(LetBinding {
+            name: node.name.clone(),
+            value: Box::new(canonicalize(&node.value)),
+            body: Box::new(canonicalize(&node.body)),
+        }),
+        Node::Def(node) => Node::Def(FunctionDefinition {
+            name: node.name.clone(),
+            params: node.params.clone(),
+            value: Box::new(canonicalize(&node.value)),
+            body: Box::new(canonicalize(&node.body)),
+        }),
+        Node::Call(node) => Node::Call(FunctionCall {
+            name: node.name.clone(),
+            args: node.args.iter().map(canonicalize).collect(),
+            host: node.host.clone(),
+        }),
+        Node::Dice(node) => Node::Dice(*node),
+    };
+    node.into()
+}
@@ -0,0 +1,104 @@
+use std::{cell::RefCell, error::Error, fmt};
+
+use super::{Evaluate, Node};
+
+/// Byte range of a sub-expression in its source string
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// What went wrong in a sub-expression
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    DivisionByZero,
+    ModuloByZero,
+    LogOfNonPositive,
+    InvalidLogBase,
+    NotANumber,
+    Overflow,
+}
+
+impl DiagnosticKind {
+    /// Errors make the result meaningless, warnings only make it suspicious
+    pub fn is_error(&self) -> bool {
+        !matches!(self, DiagnosticKind::Overflow)
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DiagnosticKind::DivisionByZero => "division by zero",
+            DiagnosticKind::ModuloByZero => "modulo by zero",
+            DiagnosticKind::LogOfNonPositive => "log of a non-positive number",
+            DiagnosticKind::InvalidLogBase => "log with a base which is non-positive or one",
+            DiagnosticKind::NotANumber => "result is not a number",
+            DiagnosticKind::Overflow => "result is infinite",
+        })
+    }
+}
+
+/// A problem found in a sub-expression during evaluation
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// Source span of the sub-expression, if it was parsed from a string
+    pub span: Option<Span>,
+    /// The sub-expression
+    pub expression: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "{} in {} at {}..{}",
+                self.kind, self.expression, span.start, span.end
+            ),
+            None => write!(f, "{} in {}", self.kind, self.expression),
+        }
+    }
+}
+
+impl Error for Diagnostic {}
+
+/// Result of a checked evaluation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+    pub value: f64,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Record what went wrong in `evaluate`, if anything, given its operands and value
+pub fn check(
+    evaluate: &Evaluate,
+    operands: &[f64],
+    value: f64,
+    diagnostics: &RefCell<Vec<Diagnostic>>,
+) {
+    let kind = match (&evaluate.node, operands) {
+        (Node::Div(_), [_, right]) if *right == 0.0 => Some(DiagnosticKind::DivisionByZero),
+        (Node::Mod(_), [_, right]) if *right == 0.0 => Some(DiagnosticKind::ModuloByZero),
+        (Node::Log(_), [left, _]) if *left <= 0.0 => Some(DiagnosticKind::LogOfNonPositive),
+        (Node::Log(_), [_, right]) if *right <= 0.0 || *right == 1.0 => {
+            Some(DiagnosticKind::InvalidLogBase)
+        }
+        _ if value.is_nan() && !operands.iter().any(|operand| operand.is_nan()) => {
+            Some(DiagnosticKind::NotANumber)
+        }
+        _ if value.is_infinite() && operands.iter().all(|operand| operand.is_finite()) => {
+            Some(DiagnosticKind::Overflow)
+        }
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        diagnostics.borrow_mut().push(Diagnostic {
+            kind,
+            span: evaluate.span,
+            expression: evaluate.to_string(),
+        });
+    }
+}
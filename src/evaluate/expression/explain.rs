@@ -0,0 +1,69 @@
+use std::fmt;
+
+use super::{Evaluate, Span};
+
+/// Value of a sub-expression, with the values it was computed from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    pub expression: String,
+    /// Source span of the sub-expression, if it was parsed from a string
+    pub span: Option<Span>,
+    pub value: f64,
+    pub children: Vec<Explanation>,
+}
+
+impl Explanation {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(
+            f,
+            "{:indent$}{} = {}",
+            "",
+            self.expression,
+            self.value,
+            indent = depth * 2
+        )?;
+        self.children
+            .iter()
+            .try_for_each(|child| child.fmt_indented(f, depth + 1))
+    }
+}
+
+/// One line per sub-expression, indented by depth
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// Explanations of the sub-expressions being evaluated, innermost last
+#[derive(Default)]
+pub struct Trace {
+    frames: Vec<Vec<Explanation>>,
+}
+
+impl Trace {
+    pub fn enter(&mut self) {
+        self.frames.push(Vec::new());
+    }
+
+    pub fn exit(&mut self, evaluate: &Evaluate, value: f64) {
+        let children = self.frames.pop().expect("Unbalanced trace");
+        let explanation = Explanation {
+            expression: evaluate.to_string(),
+            span: evaluate.span,
+            value,
+            children,
+        };
+        match self.frames.last_mut() {
+            Some(parent) => parent.push(explanation),
+            None => self.frames.push(vec![explanation]),
+        }
+    }
+
+    pub fn finish(mut self) -> Explanation {
+        self.frames
+            .pop()
+            .and_then(|mut frame| frame.pop())
+            .expect("Nothing was traced")
+    }
+}
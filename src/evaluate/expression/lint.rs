@@ -0,0 +1,226 @@
+use std::fmt;
+
+use super::{
+    analysis::{analyze_recursive, Analysis},
+    diagnostic::Span,
+    scope::Scope,
+    Evaluate, FunctionDefinition, Node,
+};
+
+/// How bad a lint is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Suspicious, but the curve may be intended
+    Warning,
+    /// The curve cannot produce a meaningful value
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// What looks wrong in a sub-expression
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LintKind {
+    DivisionByZero,
+    ModuloByZero,
+    InvalidLogBase,
+    LogOfNonPositive,
+    PossibleDivisionByZero,
+    PossibleLogOfNonPositive,
+    VariableBaseAndExponent,
+    ConstantCurve,
+}
+
+impl LintKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintKind::DivisionByZero
+            | LintKind::ModuloByZero
+            | LintKind::InvalidLogBase
+            | LintKind::LogOfNonPositive => Severity::Error,
+            LintKind::PossibleDivisionByZero
+            | LintKind::PossibleLogOfNonPositive
+            | LintKind::VariableBaseAndExponent
+            | LintKind::ConstantCurve => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LintKind::DivisionByZero => "division by zero",
+            LintKind::ModuloByZero => "modulo by zero",
+            LintKind::InvalidLogBase => "log with a base which is non-positive or one",
+            LintKind::LogOfNonPositive => "log of a non-positive number",
+            LintKind::PossibleDivisionByZero => "divisor can be zero",
+            LintKind::PossibleLogOfNonPositive => "log of a number which can be non-positive",
+            LintKind::VariableBaseAndExponent => "both base and exponent vary",
+            LintKind::ConstantCurve => "curve does not depend on x",
+        })
+    }
+}
+
+/// A suspicious construct found without evaluating
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Lint {
+    pub kind: LintKind,
+    /// Source span of the sub-expression, if it was parsed from a string
+    pub span: Option<Span>,
+    /// The sub-expression
+    pub expression: String,
+}
+
+impl Lint {
+    fn new(kind: LintKind, evaluate: &Evaluate) -> Self {
+        Self {
+            kind,
+            span: evaluate.span,
+            expression: evaluate.to_string(),
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "{}: {} in {} at {}..{}",
+                self.severity(),
+                self.kind,
+                self.expression,
+                span.start,
+                span.end
+            ),
+            None => write!(
+                f,
+                "{}: {} in {}",
+                self.severity(),
+                self.kind,
+                self.expression
+            ),
+        }
+    }
+}
+
+/// Range of a function parameter or a free variable, which is known only at the call sites
+/// or once bound
+pub const PARAMETER: Analysis = Analysis {
+    min: f64::NEG_INFINITY,
+    max: f64::INFINITY,
+    expected: f64::NAN,
+};
+
+fn is_known(analysis: &Analysis) -> bool {
+    analysis.min.is_finite() || analysis.max.is_finite()
+}
+
+fn contains_zero(analysis: &Analysis) -> bool {
+    analysis.min <= 0.0 && analysis.max >= 0.0
+}
+
+fn check(node: &Node, left: &Analysis, right: &Analysis) -> Option<LintKind> {
+    match node {
+        Node::Div(_) | Node::Mod(_) if right.is_exact() && right.min == 0.0 => Some(match node {
+            Node::Div(_) => LintKind::DivisionByZero,
+            _ => LintKind::ModuloByZero,
+        }),
+        Node::Div(_) | Node::Mod(_) if is_known(right) && contains_zero(right) => {
+            Some(LintKind::PossibleDivisionByZero)
+        }
+        Node::Log(_) if right.is_exact() && (right.min <= 0.0 || right.min == 1.0) => {
+            Some(LintKind::InvalidLogBase)
+        }
+        Node::Log(_) if left.max <= 0.0 => Some(LintKind::LogOfNonPositive),
+        Node::Log(_) if is_known(left) && left.min <= 0.0 => {
+            Some(LintKind::PossibleLogOfNonPositive)
+        }
+        Node::Pow(_)
+            if !left.is_exact() && !right.is_exact() && is_known(left) && is_known(right) =>
+        {
+            Some(LintKind::VariableBaseAndExponent)
+        }
+        _ => None,
+    }
+}
+
+pub fn lint_recursive(evaluate: &Evaluate, scope: &Scope<Analysis>, lints: &mut Vec<Lint>) {
+    match &evaluate.node {
+        Node::Add(node)
+        | Node::Sub(node)
+        | Node::Mul(node)
+        | Node::Div(node)
+        | Node::Mod(node)
+        | Node::Pow(node)
+        | Node::Log(node) => {
+            let left = analyze_recursive(node.left.as_ref(), scope);
+            let right = analyze_recursive(node.right.as_ref(), scope);
+            if let Some(kind) = check(&evaluate.node, &left, &right) {
+                lints.push(Lint::new(kind, evaluate));
+            }
+            lint_recursive(node.left.as_ref(), scope, lints);
+            lint_recursive(node.right.as_ref(), scope, lints);
+        }
+        Node::Let(node) => {
+            lint_recursive(node.value.as_ref(), scope, lints);
+            lint_recursive(
+                node.body.as_ref(),
+                &Scope::Value {
+                    parent: scope,
+                    name: &node.name,
+                    value: analyze_recursive(node.value.as_ref(), scope),
+                },
+                lints,
+            );
+        }
+        Node::Def(node) => {
+            definition_recursive(node, &node.params, scope, lints);
+            lint_recursive(
+                node.body.as_ref(),
+                &Scope::Function {
+                    parent: scope,
+                    definition: node,
+                },
+                lints,
+            );
+        }
+        _ => {
+            for child in evaluate.children() {
+                lint_recursive(child, scope, lints);
+            }
+        }
+    }
+}
+
+fn definition_recursive(
+    definition: &FunctionDefinition,
+    params: &[String],
+    scope: &Scope<Analysis>,
+    lints: &mut Vec<Lint>,
+) {
+    match params.split_first() {
+        Some((name, params)) => definition_recursive(
+            definition,
+            params,
+            &Scope::Value {
+                parent: scope,
+                name,
+                value: PARAMETER,
+            },
+            lints,
+        ),
+        None => lint_recursive(definition.value.as_ref(), scope, lints),
+    }
+}
@@ -1,4 +1,11 @@
-use nom::{branch::alt, character::streaming::space0, combinator::map, sequence::tuple, IResult};
+use nom::{
+    branch::alt,
+    character::streaming::{space0, space1},
+    combinator::{complete, map},
+    multi::{many0, separated_list0},
+    sequence::{pair, preceded, tuple},
+    IResult,
+};
 
 use super::atom::*;
 
@@ -258,6 +265,52 @@ pub enum Operation {
         left: Box<Operation>,
         right: Box<Operation>,
     },
+    Call {
+        name: String,
+        args: Vec<Operation>,
+    },
+    Let {
+        name: String,
+        value: Box<Operation>,
+        body: Box<Operation>,
+    },
+    Rand {
+        low: Box<Operation>,
+        high: Box<Operation>,
+    },
+    RandInt {
+        low: Box<Operation>,
+        high: Box<Operation>,
+    },
+    Dice {
+        count: u32,
+        sides: u32,
+    },
+    /// Offsets are counted back from the end of the input
+    Spanned {
+        operation: Box<Operation>,
+        start: usize,
+        end: usize,
+    },
+    Def {
+        name: String,
+        params: Vec<String>,
+        value: Box<Operation>,
+        body: Box<Operation>,
+    },
+}
+
+#[derive(Debug)]
+enum Statement {
+    Let {
+        name: String,
+        value: Box<Operation>,
+    },
+    Def {
+        name: String,
+        params: Vec<String>,
+        value: Box<Operation>,
+    },
 }
 
 pub fn n(input: &str) -> IResult<&str, Box<Operation>> {
@@ -272,6 +325,100 @@ pub fn v(input: &str) -> IResult<&str, Box<Operation>> {
     })(input)
 }
 
+/// A number, a variable or an operation
+pub fn operand(input: &str) -> IResult<&str, Box<Operation>> {
+    alt((
+        operation,
+        map(number, |num| Box::new(Operation::N(num))),
+        map(variable, |var| Box::new(Operation::V(var))),
+    ))(input)
+}
+
+fn separator(input: &str) -> IResult<&str, (&str, Comma, &str)> {
+    tuple((space0, comma, space0))(input)
+}
+
+pub fn call(input: &str) -> IResult<&str, Box<Operation>> {
+    map(
+        tuple((
+            identifier,
+            p_left,
+            space0,
+            separated_list0(separator, map(operand, |arg| *arg)),
+            space0,
+            p_right,
+        )),
+        |(name, _, _, args, _, _)| Box::new(Operation::Call { name, args }),
+    )(input)
+}
+
+fn random_range(input: &str) -> IResult<&str, (Box<Operation>, Box<Operation>)> {
+    map(
+        tuple((p_left, space0, operand, separator, operand, space0, p_right)),
+        |(_, _, low, _, high, _, _)| (low, high),
+    )(input)
+}
+
+/// `randint(low, high)`, `rand(low, high)` or a dice term
+pub fn random(input: &str) -> IResult<&str, Box<Operation>> {
+    alt((
+        map(preceded(rand_int_keyword, random_range), |(low, high)| {
+            Box::new(Operation::RandInt { low, high })
+        }),
+        map(preceded(rand_keyword, random_range), |(low, high)| {
+            Box::new(Operation::Rand { low, high })
+        }),
+        map(dice, |(count, sides)| {
+            Box::new(Operation::Dice { count, sides })
+        }),
+    ))(input)
+}
+
+fn let_statement(input: &str) -> IResult<&str, Statement> {
+    map(
+        tuple((
+            let_keyword,
+            space1,
+            identifier,
+            space0,
+            assign,
+            space0,
+            operand,
+            space0,
+            semicolon,
+            space0,
+        )),
+        |(_, _, name, _, _, _, value, _, _, _)| Statement::Let { name, value },
+    )(input)
+}
+
+fn def_statement(input: &str) -> IResult<&str, Statement> {
+    map(
+        tuple((
+            def_keyword,
+            space1,
+            identifier,
+            p_left,
+            space0,
+            separated_list0(separator, identifier),
+            space0,
+            p_right,
+            space0,
+            assign,
+            space0,
+            operand,
+            space0,
+            semicolon,
+            space0,
+        )),
+        |(_, _, name, _, _, params, _, _, _, _, _, value, _, _, _)| Statement::Def {
+            name,
+            params,
+            value,
+        },
+    )(input)
+}
+
 macro_rules! bi_operation {
     ($fname:ident, $left_func:ident, $oper_func:ident, $right_func:ident, $left_type:ty, $oper_type:ident, $right_type:ty, $oper:ident) => {
         fn $fname(input: &str) -> IResult<&str, Box<Operation>> {
@@ -684,9 +831,11 @@ bi_operation!(
 );
 
 pub fn operation(input: &str) -> IResult<&str, Box<Operation>> {
-    alt((
+    let (rest, operation) = alt((
         n,
         v,
+        call,
+        random,
         alt((
             n_add_n, n_add_v, n_add_o, v_add_n, v_add_v, v_add_o, o_add_n, o_add_v, o_add_o,
         )),
@@ -708,5 +857,49 @@ pub fn operation(input: &str) -> IResult<&str, Box<Operation>> {
         alt((
             n_log_n, n_log_v, n_log_o, v_log_n, v_log_v, v_log_o, o_log_n, o_log_v, o_log_o,
         )),
+    ))(input)?;
+    Ok((
+        rest,
+        Box::new(Operation::Spanned {
+            operation,
+            start: input.len(),
+            end: rest.len(),
+        }),
+    ))
+}
+
+/// A number, a variable or an operation which may end the input
+fn body(input: &str) -> IResult<&str, Box<Operation>> {
+    alt((
+        complete(operation),
+        map(last_number, |num| Box::new(Operation::N(num))),
+        map(variable, |var| Box::new(Operation::V(var))),
     ))(input)
 }
+
+/// Any number of `let` and `def` statements followed by a number, a variable or an operation
+pub fn program(input: &str) -> IResult<&str, Box<Operation>> {
+    map(
+        pair(many0(alt((let_statement, def_statement))), body),
+        |(statements, body)| {
+            statements
+                .into_iter()
+                .rev()
+                .fold(body, |body, statement| match statement {
+                    Statement::Let { name, value } => {
+                        Box::new(Operation::Let { name, value, body })
+                    }
+                    Statement::Def {
+                        name,
+                        params,
+                        value,
+                    } => Box::new(Operation::Def {
+                        name,
+                        params,
+                        value,
+                        body,
+                    }),
+                })
+        },
+    )(input)
+}
@@ -0,0 +1,138 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::{BinaryOperation, Evaluate, FunctionCall, FunctionDefinition, LetBinding, Node};
+
+fn number(evaluate: &Evaluate) -> Option<f64> {
+    match evaluate.node {
+        Node::Number(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// Run `f` with `names` bound to `value`, or unbound if `value` is `None`
+fn shadowed<R>(
+    values: &mut HashMap<String, f64>,
+    names: &[String],
+    value: Option<f64>,
+    f: impl FnOnce(&mut HashMap<String, f64>) -> R,
+) -> R {
+    let saved: Vec<(String, Option<f64>)> = names
+        .iter()
+        .map(|name| {
+            let saved = match value {
+                Some(value) => values.insert(name.clone(), value),
+                None => values.remove(name),
+            };
+            (name.clone(), saved)
+        })
+        .collect();
+    let result = f(values);
+    for (name, saved) in saved.into_iter().rev() {
+        match saved {
+            Some(saved) => values.insert(name, saved),
+            None => values.remove(&name),
+        };
+    }
+    result
+}
+
+/// Substitute `values` for free variables and fold constant arithmetic
+pub fn bind(evaluate: &Evaluate, values: &mut HashMap<String, f64>) -> Evaluate {
+    let binary = |node: &BinaryOperation, values: &mut HashMap<String, f64>| {
+        BinaryOperation::new(bind(&node.left, values), bind(&node.right, values))
+    };
+    let node = match &evaluate.node {
+        Node::Add(node)
+        | Node::Sub(node)
+        | Node::Mul(node)
+        | Node::Div(node)
+        | Node::Mod(node)
+        | Node::Pow(node)
+        | Node::Log(node) => {
+            let operation = binary(node, values);
+            if let (Some(left), Some(right)) = (number(&operation.left), number(&operation.right)) {
+                return evaluate.node.apply(left, right).into();
+            }
+            match &evaluate.node {
+                Node::Add(_) => Node::Add(operation),
+                Node::Sub(_) => Node::Sub(operation),
+                Node::Mul(_) => Node::Mul(operation),
+                Node::Div(_) => Node::Div(operation),
+                Node::Mod(_) => Node::Mod(operation),
+                Node::Pow(_) => Node::Pow(operation),
+                _ => Node::Log(operation),
+            }
+        }
+        Node::Rand(node) => Node::Rand(binary(node, values)),
+        Node::RandInt(node) => Node::RandInt(binary(node, values)),
+        Node::Number(_) | Node::Dice(_) => evaluate.node.clone(),
+        Node::Variable(name) => match values.get(name) {
+            Some(value) => return (*value).into(),
+            None => evaluate.node.clone(),
+        },
+        Node::Let(node) => {
+            let value = bind(&node.value, values);
+            let constant = number(&value);
+            let body = shadowed(
+                values,
+                std::slice::from_ref(&node.name),
+                constant,
+                |values| bind(&node.body, values),
+            );
+            if constant.is_some() {
+                return body;
+            }
+            Node::Let(LetBinding {
+                name: node.name.clone(),
+                value: Box::new(value),
+                body: Box::new(body),
+            })
+        }
+        Node::Def(node) => Node::Def(FunctionDefinition {
+            name: node.name.clone(),
+            params: node.params.clone(),
+            value: Box::new(shadowed(values, &node.params, None, |values| {
+                bind(&node.value, values)
+            })),
+            body: Box::new(bind(&node.body, values)),
+        }),
+        Node::Call(node) => Node::Call(FunctionCall {
+            name: node.name.clone(),
+            args: node.args.iter().map(|arg| bind(arg, values)).collect(),
+            host: node.host.clone(),
+        }),
+    };
+    Evaluate {
+        node,
+        span: evaluate.span,
+    }
+}
+
+/// Collect the variables of `evaluate` which are not bound inside it
+pub fn free_variables(evaluate: &Evaluate, bound: &mut Vec<String>, free: &mut BTreeSet<String>) {
+    match &evaluate.node {
+        Node::Variable(name) => {
+            if !bound.contains(name) {
+                free.insert(name.clone());
+            }
+        }
+        Node::Let(node) => {
+            free_variables(&node.value, bound, free);
+            bound.push(node.name.clone());
+            free_variables(&node.body, bound, free);
+            bound.pop();
+        }
+        Node::Def(node) => {
+            let depth = bound.len();
+            bound.extend(node.params.iter().cloned());
+            free_variables(&node.value, bound, free);
+            bound.truncate(depth);
+            free_variables(&node.body, bound, free);
+        }
+        _ => {
+            for child in evaluate.children() {
+                free_variables(child, bound, free);
+            }
+        }
+    }
+}
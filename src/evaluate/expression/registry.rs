@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    panic::{RefUnwindSafe, UnwindSafe},
+    sync::Arc,
+};
+
+use super::atom;
+
+type Function = dyn Fn(&[f64]) -> f64 + Send + Sync + RefUnwindSafe + UnwindSafe;
+
+/// A host function callable from expressions
+#[derive(Clone)]
+pub struct HostFunction {
+    arity: usize,
+    function: Arc<Function>,
+}
+
+impl HostFunction {
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn call(&self, args: &[f64]) -> f64 {
+        (self.function)(args)
+    }
+}
+
+/// Host functions are equal if they share the same registered closure
+impl PartialEq for HostFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.arity == other.arity
+            && Arc::as_ptr(&self.function) as *const () == Arc::as_ptr(&other.function) as *const ()
+    }
+}
+
+impl Eq for HostFunction {}
+
+impl Hash for HostFunction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.arity.hash(state);
+    }
+}
+
+impl fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostFunction")
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Named Rust functions which expressions may call
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::Evaluate as Super;
+/// use flp_math::evaluate::expression::{Evaluate, FunctionRegistry};
+/// let mut registry = FunctionRegistry::new();
+/// registry.register("item_tier", 1, |args| (args[0] / 10.0).floor()).unwrap();
+/// let evaluate = Evaluate::try_from_str_with("(item_tier(x) * 100)", &registry).unwrap();
+/// assert_eq!(Super::<u8, u16>::evaluate(&evaluate, 25), 200);
+/// assert!(Evaluate::try_from_str_with("(zone_modifier(x) * 100)", &registry).is_err());
+/// assert!(Evaluate::try_from_str_with("(item_tier(x, 2) * 100)", &registry).is_err());
+/// assert!(registry.register("rand", 0, |_| 4.0).is_err());
+/// assert!(registry.register("2x", 1, |args| args[0] * 2.0).is_err());
+///
+/// let curve = Evaluate::try_from_str_with("(item_tier(x) - 1)", &registry).unwrap();
+/// let value = std::panic::catch_unwind(|| Super::<u8, u16>::evaluate(&curve, 5));
+/// assert!(value.is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, HostFunction>,
+    random: bool,
+}
+
+impl FunctionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a function, replacing any function with the same name
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - name used to call the function
+    /// - `arity` - number of arguments the function takes
+    /// - `function` - the function, called with exactly `arity` arguments
+    ///
+    /// # Errors
+    ///
+    /// If `name` is a reserved word or could not be called from an expression
+    pub fn register<F>(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: F,
+    ) -> Result<&mut Self, String>
+    where
+        F: Fn(&[f64]) -> f64 + Send + Sync + RefUnwindSafe + UnwindSafe + 'static,
+    {
+        if !atom::is_name(name) {
+            return Err(format!("Invalid function name: {name}"));
+        }
+        self.functions.insert(
+            name.to_string(),
+            HostFunction {
+                arity,
+                function: Arc::new(function),
+            },
+        );
+        Ok(self)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&HostFunction> {
+        self.functions.get(name)
+    }
+
+    /// Allow the random terms `rand(low, high)`, `randint(low, high)` and dice `NdM`
+    ///
+    /// Expressions with random terms can only be evaluated with
+    /// [`Evaluate::evaluate_with_rng`](super::Evaluate::evaluate_with_rng), any other
+    /// evaluation panics.
+    pub fn allow_random(&mut self) -> &mut Self {
+        self.random = true;
+        self
+    }
+
+    pub fn random(&self) -> bool {
+        self.random
+    }
+}
@@ -0,0 +1,128 @@
+//! Reverse Polish notation for expressions
+//!
+//! Tokens are separated by whitespace. Operators are `+ - * / % ^ log`, random
+//! terms are `rand`, `randint` and dice `NdM`, and registered functions pop
+//! as many operands as they take. Any other name is a variable.
+//!
+//! # Examples
+//!
+//! ```
+//! use flp_math::evaluate::expression::{rpn, Evaluate};
+//! let evaluate = rpn::parse("2 x * 3 +").unwrap();
+//! assert_eq!(evaluate.to_string(), "((2 * x) + 3)");
+//! let evaluate = Evaluate::try_from_str("((x ^ 2) log 10)").unwrap();
+//! assert_eq!(rpn::print(&evaluate).unwrap(), "x 2 ^ 10 log");
+//! assert!(rpn::parse("2 +").is_err());
+//! ```
+
+use super::{
+    tree::Tree::{self, *},
+    Evaluate, FunctionRegistry,
+};
+
+/// Pop the last `count` operands, in order
+fn pop(stack: &mut Vec<Tree>, count: usize, token: &str) -> Result<Vec<Tree>, String> {
+    if stack.len() < count {
+        Err(format!("Not enough operands for {token}"))
+    } else {
+        Ok(stack.split_off(stack.len() - count))
+    }
+}
+
+pub fn parse(s: &str) -> Result<Evaluate, String> {
+    parse_with(s, &FunctionRegistry::default())
+}
+
+/// Parse an expression which may call the functions in `registry`
+pub fn parse_with(s: &str, registry: &FunctionRegistry) -> Result<Evaluate, String> {
+    let mut stack: Vec<Tree> = Vec::new();
+    for token in s.split_whitespace() {
+        let tree = match token {
+            "+" | "-" | "*" | "/" | "%" | "^" | "log" | "rand" | "randint" => {
+                let right = Box::new(pop(&mut stack, 1, token)?.remove(0));
+                let left = Box::new(pop(&mut stack, 1, token)?.remove(0));
+                match token {
+                    "+" => Add { left, right },
+                    "-" => Sub { left, right },
+                    "*" => Mul { left, right },
+                    "/" => Div { left, right },
+                    "%" => Mod { left, right },
+                    "^" => Pow { left, right },
+                    "log" => Log { left, right },
+                    "rand" => Rand {
+                        low: left,
+                        high: right,
+                    },
+                    _ => RandInt {
+                        low: left,
+                        high: right,
+                    },
+                }
+            }
+            _ => match registry.get(token) {
+                Some(function) => Call {
+                    name: token.to_string(),
+                    args: pop(&mut stack, function.arity(), token)?,
+                },
+                None => Tree::from_token(token)?,
+            },
+        };
+        stack.push(tree);
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(tree), true) => tree.try_into_evaluate_with(registry),
+        (None, _) => Err("Empty expression".to_string()),
+        (Some(_), false) => Err("Too many operands".to_string()),
+    }
+}
+
+fn print_recursive(tree: &Tree, tokens: &mut Vec<String>) -> Result<(), String> {
+    let (left, right, operator) = match tree {
+        Add { left, right } => (left, right, "+"),
+        Sub { left, right } => (left, right, "-"),
+        Mul { left, right } => (left, right, "*"),
+        Div { left, right } => (left, right, "/"),
+        Mod { left, right } => (left, right, "%"),
+        Pow { left, right } => (left, right, "^"),
+        Log { left, right } => (left, right, "log"),
+        Rand { low, high } => (low, high, "rand"),
+        RandInt { low, high } => (low, high, "randint"),
+        Number { value } => {
+            tokens.push(value.to_string());
+            return Ok(());
+        }
+        Variable { name } => {
+            tokens.push(name.clone());
+            return Ok(());
+        }
+        Dice { count, sides } => {
+            tokens.push(format!("{count}d{sides}"));
+            return Ok(());
+        }
+        Call { name, args } => {
+            for arg in args {
+                print_recursive(arg, tokens)?;
+            }
+            tokens.push(name.clone());
+            return Ok(());
+        }
+        Let { .. } | Def { .. } => {
+            return Err("Cannot write let or def statements in RPN".to_string())
+        }
+    };
+    print_recursive(left, tokens)?;
+    print_recursive(right, tokens)?;
+    tokens.push(operator.to_string());
+    Ok(())
+}
+
+/// Write an expression in RPN
+///
+/// Fails on `let` and `def` statements, which RPN cannot express.
+/// Functions calls are written as their name after their arguments, and can
+/// only be read back with a registry providing them.
+pub fn print(evaluate: &Evaluate) -> Result<String, String> {
+    let mut tokens = Vec::new();
+    print_recursive(&evaluate.into(), &mut tokens)?;
+    Ok(tokens.join(" "))
+}
@@ -0,0 +1,240 @@
+use std::cell::{RefCell, RefMut};
+
+use rand::RngCore;
+
+use super::{
+    atom, diagnostic::Diagnostic, explain::Trace, Evaluate, FunctionDefinition, FunctionRegistry,
+    Node,
+};
+
+/// Name of the variable bound to the evaluated input
+pub const INPUT: &str = "x";
+
+/// Random number generator shared by every scope of one evaluation
+pub trait SharedRng {
+    fn borrow_rng(&self) -> RefMut<'_, dyn RngCore + '_>;
+}
+
+impl<R> SharedRng for RefCell<R>
+where
+    R: RngCore,
+{
+    fn borrow_rng(&self) -> RefMut<'_, dyn RngCore + '_> {
+        RefMut::map(self.borrow_mut(), |rng| rng as &mut dyn RngCore)
+    }
+}
+
+/// Names visible while evaluating a node
+///
+/// # Generic
+///
+/// - `T` - value bound to each name
+pub enum Scope<'a, T = f64> {
+    Root {
+        value: T,
+        rng: Option<&'a dyn SharedRng>,
+        diagnostics: Option<&'a RefCell<Vec<Diagnostic>>>,
+        trace: Option<&'a RefCell<Trace>>,
+    },
+    Value {
+        parent: &'a Scope<'a, T>,
+        name: &'a str,
+        value: T,
+    },
+    Function {
+        parent: &'a Scope<'a, T>,
+        definition: &'a FunctionDefinition,
+    },
+}
+
+impl<'a, T> Scope<'a, T>
+where
+    T: Clone + Copy,
+{
+    pub fn root(value: T) -> Self {
+        Scope::Root {
+            value,
+            rng: None,
+            diagnostics: None,
+            trace: None,
+        }
+    }
+
+    /// Look up the innermost value bound to `name`
+    pub fn value(&self, name: &str) -> Option<T> {
+        match self {
+            Scope::Root { value, .. } => (name == INPUT).then_some(*value),
+            Scope::Value {
+                parent,
+                name: bound,
+                value,
+            } => {
+                if *bound == name {
+                    Some(*value)
+                } else {
+                    parent.value(name)
+                }
+            }
+            Scope::Function { parent, .. } => parent.value(name),
+        }
+    }
+
+    /// Call `f` with this scope extended by `value` bound to every name in `names`
+    pub fn with_values<R>(&self, names: &[String], value: T, f: impl FnOnce(&Scope<T>) -> R) -> R {
+        match names.split_first() {
+            Some((name, names)) => Scope::Value {
+                parent: self,
+                name,
+                value,
+            }
+            .with_values(names, value, f),
+            None => f(self),
+        }
+    }
+
+    /// Look up the innermost function called `name`, together with the scope it was defined in
+    pub fn function(&self, name: &str) -> Option<(&'a FunctionDefinition, &'a Scope<'a, T>)> {
+        match self {
+            Scope::Root { .. } => None,
+            Scope::Value { parent, .. } => parent.function(name),
+            Scope::Function { parent, definition } => {
+                if definition.name == name {
+                    Some((definition, parent))
+                } else {
+                    parent.function(name)
+                }
+            }
+        }
+    }
+
+    /// The random number generator of this evaluation, if any
+    pub fn rng(&self) -> Option<&'a dyn SharedRng> {
+        match self {
+            Scope::Root { rng, .. } => *rng,
+            Scope::Value { parent, .. } | Scope::Function { parent, .. } => parent.rng(),
+        }
+    }
+
+    /// Where problems found during this evaluation are recorded, if anywhere
+    pub fn diagnostics(&self) -> Option<&'a RefCell<Vec<Diagnostic>>> {
+        match self {
+            Scope::Root { diagnostics, .. } => *diagnostics,
+            Scope::Value { parent, .. } | Scope::Function { parent, .. } => parent.diagnostics(),
+        }
+    }
+
+    /// Where the values of sub-expressions are recorded, if anywhere
+    pub fn trace(&self) -> Option<&'a RefCell<Trace>> {
+        match self {
+            Scope::Root { trace, .. } => *trace,
+            Scope::Value { parent, .. } | Scope::Function { parent, .. } => parent.trace(),
+        }
+    }
+}
+
+/// A name visible at parse time
+pub enum Name {
+    Value(String),
+    Function(String, usize),
+}
+
+/// Check that every variable and function call refers to a visible name,
+/// and link calls which are not defined in the expression to `registry`
+///
+/// # Arguments
+///
+/// - `evaluate` - the expression to resolve
+/// - `names` - names visible to `evaluate`, innermost last
+/// - `registry` - host functions visible everywhere
+pub fn resolve(
+    evaluate: &mut Evaluate,
+    names: &mut Vec<Name>,
+    registry: &FunctionRegistry,
+) -> Result<(), String> {
+    match &mut evaluate.node {
+        Node::Rand(_) | Node::RandInt(_) | Node::Dice(_) if !registry.random() => {
+            Err(format!("Random terms are not allowed: {evaluate}"))
+        }
+        Node::Dice(node) if node.sides == 0 => Err(format!("Invalid dice: {evaluate}")),
+        Node::Add(node)
+        | Node::Sub(node)
+        | Node::Mul(node)
+        | Node::Div(node)
+        | Node::Mod(node)
+        | Node::Pow(node)
+        | Node::Log(node)
+        | Node::Rand(node)
+        | Node::RandInt(node) => {
+            resolve(node.left.as_mut(), names, registry)?;
+            resolve(node.right.as_mut(), names, registry)
+        }
+        Node::Number(_) | Node::Dice(_) => Ok(()),
+        Node::Variable(name) if !atom::is_name(name) => {
+            Err(format!("Invalid variable name: {name}"))
+        }
+        Node::Variable(name) => {
+            let bound = name == INPUT
+                || names
+                    .iter()
+                    .any(|visible| matches!(visible, Name::Value(visible) if visible == name));
+            if bound {
+                Ok(())
+            } else {
+                Err(format!("Unbound variable: {name}"))
+            }
+        }
+        Node::Let(node) => {
+            if !atom::is_name(&node.name) {
+                return Err(format!("Invalid variable name: {}", node.name));
+            }
+            resolve(node.value.as_mut(), names, registry)?;
+            names.push(Name::Value(node.name.clone()));
+            let result = resolve(node.body.as_mut(), names, registry);
+            names.pop();
+            result
+        }
+        Node::Def(node) => {
+            if !atom::is_name(&node.name) {
+                return Err(format!("Invalid function name: {}", node.name));
+            }
+            if let Some(param) = node.params.iter().find(|param| !atom::is_name(param)) {
+                return Err(format!("Invalid variable name: {param}"));
+            }
+            let depth = names.len();
+            names.extend(node.params.iter().cloned().map(Name::Value));
+            let result = resolve(node.value.as_mut(), names, registry);
+            names.truncate(depth);
+            result?;
+            names.push(Name::Function(node.name.clone(), node.params.len()));
+            let result = resolve(node.body.as_mut(), names, registry);
+            names.pop();
+            result
+        }
+        Node::Call(node) => {
+            let defined = names.iter().rev().find_map(|visible| match visible {
+                Name::Function(visible, arity) if *visible == node.name => Some(*arity),
+                _ => None,
+            });
+            let arity = match defined {
+                Some(arity) => arity,
+                None => {
+                    let host = registry
+                        .get(&node.name)
+                        .ok_or_else(|| format!("Unknown function: {}", node.name))?;
+                    node.host = Some(host.clone());
+                    host.arity()
+                }
+            };
+            if arity != node.args.len() {
+                return Err(format!(
+                    "Function {} expects {arity} arguments, got {}",
+                    node.name,
+                    node.args.len()
+                ));
+            }
+            node.args
+                .iter_mut()
+                .try_for_each(|arg| resolve(arg, names, registry))
+        }
+    }
+}
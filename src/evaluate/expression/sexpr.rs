@@ -0,0 +1,187 @@
+//! S-expressions for expressions
+//!
+//! Operators are `+ - * / % ^ log rand randint`, where `+` and `*` take two or
+//! more operands and the others take two. Bindings are written
+//! `(let name value body)` and `(def name (params) value body)`, and any other
+//! list is a function call.
+//!
+//! # Examples
+//!
+//! ```
+//! use flp_math::evaluate::expression::{sexpr, Evaluate};
+//! let evaluate = sexpr::parse("(+ (* 2 x) 3)").unwrap();
+//! assert_eq!(evaluate.to_string(), "((2 * x) + 3)");
+//! let evaluate = sexpr::parse("(def f (a b) (- a b) (f x 1))").unwrap();
+//! assert_eq!(evaluate.to_string(), "def f(a, b) = (a - b); f(x, 1)");
+//! let evaluate = Evaluate::try_from_str("let a = (x + 1); (a log 2)").unwrap();
+//! assert_eq!(sexpr::print(&evaluate), "(let a (+ x 1) (log a 2))");
+//! assert!(sexpr::parse("(- 1 2 3)").is_err());
+//! ```
+
+use nom::{
+    branch::alt,
+    bytes::complete::is_not,
+    character::complete::{char, multispace0},
+    combinator::map,
+    multi::many0,
+    sequence::{delimited, preceded},
+    IResult,
+};
+
+use super::{
+    atom::RESERVED,
+    tree::Tree::{self, *},
+    Evaluate, FunctionRegistry,
+};
+
+enum SExpr<'a> {
+    Atom(&'a str),
+    List(Vec<SExpr<'a>>),
+}
+
+fn sexpr(input: &str) -> IResult<&str, SExpr<'_>> {
+    preceded(
+        multispace0,
+        alt((
+            map(
+                delimited(char('('), many0(sexpr), preceded(multispace0, char(')'))),
+                SExpr::List,
+            ),
+            map(is_not(" \t\r\n()"), SExpr::Atom),
+        )),
+    )(input)
+}
+
+fn name(sexpr: &SExpr) -> Result<String, String> {
+    match sexpr {
+        SExpr::Atom(token) => match Tree::from_token(token)? {
+            Variable { name } => Ok(name),
+            _ => Err(format!("Invalid name: {token}")),
+        },
+        SExpr::List(_) => Err("Expected a name, found a list".to_string()),
+    }
+}
+
+fn build(sexpr: &SExpr) -> Result<Tree, String> {
+    let items = match sexpr {
+        SExpr::Atom(token) => return Tree::from_token(token),
+        SExpr::List(items) => items,
+    };
+    let (head, rest) = match items.split_first() {
+        Some((SExpr::Atom(head), rest)) => (*head, rest),
+        Some((SExpr::List(_), _)) => return Err("Expected an operator or a name".to_string()),
+        None => return Err("Empty list".to_string()),
+    };
+    match (head, rest) {
+        ("let", [binding, value, body]) => {
+            return Ok(Let {
+                name: name(binding)?,
+                value: Box::new(build(value)?),
+                body: Box::new(build(body)?),
+            })
+        }
+        ("def", [function, SExpr::List(params), value, body]) => {
+            return Ok(Def {
+                name: name(function)?,
+                params: params.iter().map(name).collect::<Result<_, _>>()?,
+                value: Box::new(build(value)?),
+                body: Box::new(build(body)?),
+            })
+        }
+        ("let" | "def", _) => return Err(format!("Malformed {head}")),
+        _ => {}
+    }
+    let args = rest.iter().map(build).collect::<Result<Vec<_>, _>>()?;
+    match head {
+        "+" | "*" if args.len() >= 2 => {
+            let mut args = args.into_iter();
+            let first = args.next().unwrap();
+            Ok(args.fold(first, |left, right| {
+                let (left, right) = (Box::new(left), Box::new(right));
+                if head == "+" {
+                    Add { left, right }
+                } else {
+                    Mul { left, right }
+                }
+            }))
+        }
+        "+" | "*" => Err(format!("{head} takes two or more operands")),
+        "-" | "/" | "%" | "^" | "log" | "rand" | "randint" => {
+            let [left, right]: [Tree; 2] = args
+                .try_into()
+                .map_err(|_| format!("{head} takes two operands"))?;
+            let (left, right) = (Box::new(left), Box::new(right));
+            Ok(match head {
+                "-" => Sub { left, right },
+                "/" => Div { left, right },
+                "%" => Mod { left, right },
+                "^" => Pow { left, right },
+                "log" => Log { left, right },
+                "rand" => Rand {
+                    low: left,
+                    high: right,
+                },
+                _ => RandInt {
+                    low: left,
+                    high: right,
+                },
+            })
+        }
+        _ if RESERVED.contains(&head) => Err(format!("Unexpected {head}")),
+        _ => Ok(Call {
+            name: name(&SExpr::Atom(head))?,
+            args,
+        }),
+    }
+}
+
+pub fn parse(s: &str) -> Result<Evaluate, String> {
+    parse_with(s, &FunctionRegistry::default())
+}
+
+/// Parse an expression which may call the functions in `registry`
+pub fn parse_with(s: &str, registry: &FunctionRegistry) -> Result<Evaluate, String> {
+    let (rest, sexpr) = sexpr(s).map_err(|err| err.to_string())?;
+    if !rest.trim().is_empty() {
+        return Err(format!("Unexpected input: {}", rest.trim()));
+    }
+    build(&sexpr)?.try_into_evaluate_with(registry)
+}
+
+fn print_recursive(tree: &Tree) -> String {
+    let list = |head: &str, items: &[&Tree]| {
+        let items: Vec<String> = items.iter().map(|item| print_recursive(item)).collect();
+        format!("({head} {})", items.join(" "))
+    };
+    match tree {
+        Add { left, right } => list("+", &[left, right]),
+        Sub { left, right } => list("-", &[left, right]),
+        Mul { left, right } => list("*", &[left, right]),
+        Div { left, right } => list("/", &[left, right]),
+        Mod { left, right } => list("%", &[left, right]),
+        Pow { left, right } => list("^", &[left, right]),
+        Log { left, right } => list("log", &[left, right]),
+        Rand { low, high } => list("rand", &[low, high]),
+        RandInt { low, high } => list("randint", &[low, high]),
+        Number { value } => value.to_string(),
+        Variable { name } => name.clone(),
+        Dice { count, sides } => format!("{count}d{sides}"),
+        Let { name, value, body } => list(&format!("let {name}"), &[value, body]),
+        Def {
+            name,
+            params,
+            value,
+            body,
+        } => list(
+            &format!("def {name} ({})", params.join(" ")),
+            &[value, body],
+        ),
+        Call { name, args } if args.is_empty() => format!("({name})"),
+        Call { name, args } => list(name, &args.iter().collect::<Vec<_>>()),
+    }
+}
+
+/// Write an expression as an S-expression
+pub fn print(evaluate: &Evaluate) -> String {
+    print_recursive(&evaluate.into())
+}
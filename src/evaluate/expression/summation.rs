@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+use super::{scope::INPUT, BinaryOperation, Evaluate, Node};
+
+/// Highest integer power expanded when raising a series to a constant
+const MAX_POWER: f64 = 64.0;
+
+/// `Σ polynomial[i] * x^i + Σ coefficient * ratio^x`
+#[derive(Clone, Debug, Default)]
+struct Series {
+    polynomial: Vec<f64>,
+    geometric: Vec<(f64, f64)>,
+}
+
+impl Series {
+    fn constant(value: f64) -> Self {
+        Self {
+            polynomial: vec![value],
+            geometric: Vec::new(),
+        }
+    }
+
+    fn variable() -> Self {
+        Self {
+            polynomial: vec![0.0, 1.0],
+            geometric: Vec::new(),
+        }
+    }
+
+    /// Value of the series if it does not depend on x
+    fn as_constant(&self) -> Option<f64> {
+        let constant = self
+            .geometric
+            .iter()
+            .all(|(coefficient, _)| *coefficient == 0.0)
+            && self.polynomial.iter().skip(1).all(|c| *c == 0.0);
+        constant.then(|| self.polynomial.first().copied().unwrap_or(0.0))
+    }
+
+    fn scale(mut self, factor: f64) -> Self {
+        self.polynomial.iter_mut().for_each(|c| *c *= factor);
+        self.geometric
+            .iter_mut()
+            .for_each(|(coefficient, _)| *coefficient *= factor);
+        self
+    }
+
+    fn add(mut self, other: Self) -> Self {
+        if self.polynomial.len() < other.polynomial.len() {
+            self.polynomial.resize(other.polynomial.len(), 0.0);
+        }
+        for (i, c) in other.polynomial.into_iter().enumerate() {
+            self.polynomial[i] += c;
+        }
+        for (coefficient, ratio) in other.geometric {
+            match self.geometric.iter_mut().find(|(_, r)| *r == ratio) {
+                Some((c, _)) => *c += coefficient,
+                None => self.geometric.push((coefficient, ratio)),
+            }
+        }
+        self
+    }
+
+    fn mul(self, other: Self) -> Result<Self, String> {
+        if let Some(factor) = other.as_constant() {
+            return Ok(self.scale(factor));
+        }
+        if let Some(factor) = self.as_constant() {
+            return Ok(other.scale(factor));
+        }
+        let has_geometric = |series: &Self| series.geometric.iter().any(|(c, _)| *c != 0.0);
+        let has_polynomial = |series: &Self| series.polynomial.iter().skip(1).any(|c| *c != 0.0);
+        if (has_geometric(&self) && has_polynomial(&other))
+            || (has_polynomial(&self) && has_geometric(&other))
+        {
+            return Err("Cannot sum a polynomial multiplied by a geometric term".to_string());
+        }
+        let mut polynomial = vec![0.0; self.polynomial.len() + other.polynomial.len()];
+        for (i, left) in self.polynomial.iter().enumerate() {
+            for (j, right) in other.polynomial.iter().enumerate() {
+                polynomial[i + j] += left * right;
+            }
+        }
+        let constant = |series: &Self| series.polynomial.first().copied().unwrap_or(0.0);
+        let mut result = Self {
+            polynomial,
+            geometric: Vec::new(),
+        };
+        result = result.add(
+            Self {
+                polynomial: Vec::new(),
+                geometric: other.geometric.clone(),
+            }
+            .scale(constant(&self)),
+        );
+        result = result.add(
+            Self {
+                polynomial: Vec::new(),
+                geometric: self.geometric.clone(),
+            }
+            .scale(constant(&other)),
+        );
+        for (left_coefficient, left_ratio) in &self.geometric {
+            for (right_coefficient, right_ratio) in &other.geometric {
+                result = result.add(Self {
+                    polynomial: Vec::new(),
+                    geometric: vec![(
+                        left_coefficient * right_coefficient,
+                        left_ratio * right_ratio,
+                    )],
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    fn pow(self, exponent: Self) -> Result<Self, String> {
+        match (self.as_constant(), exponent.as_constant()) {
+            (Some(base), Some(exponent)) => Ok(Self::constant(base.powf(exponent))),
+            (_, Some(exponent)) => {
+                if exponent < 0.0 || exponent.fract() != 0.0 || exponent > MAX_POWER {
+                    return Err(format!("Cannot sum a series raised to {exponent}"));
+                }
+                (0..exponent as u32)
+                    .try_fold(Self::constant(1.0), |power, _| power.mul(self.clone()))
+            }
+            (Some(base), None) => {
+                if !exponent.geometric.is_empty() || exponent.polynomial.len() > 2 {
+                    return Err("Cannot sum a constant raised to a nonlinear exponent".to_string());
+                }
+                let offset = exponent.polynomial.first().copied().unwrap_or(0.0);
+                let slope = exponent.polynomial.get(1).copied().unwrap_or(0.0);
+                Ok(Self {
+                    polynomial: Vec::new(),
+                    geometric: vec![(base.powf(offset), base.powf(slope))],
+                })
+            }
+            (None, None) => Err("Cannot sum a series raised to a variable".to_string()),
+        }
+    }
+
+    /// The series of prefix sums `Σ_{k=1}^{x} self(k)`
+    fn prefix_sum(&self) -> Self {
+        let bernoulli = bernoulli_numbers(self.polynomial.len());
+        let mut result = Self::default();
+        for (p, c) in self.polynomial.iter().enumerate() {
+            // Faulhaber's formula: Σ k^p = 1/(p+1) Σ_j C(p+1, j) B_j n^(p+1-j)
+            let mut polynomial = vec![0.0; p + 2];
+            for (j, b) in bernoulli.iter().enumerate().take(p + 1) {
+                polynomial[p + 1 - j] = c * binomial(p + 1, j) * b / (p + 1) as f64;
+            }
+            result = result.add(Self {
+                polynomial,
+                geometric: Vec::new(),
+            });
+        }
+        for (coefficient, ratio) in &self.geometric {
+            if *ratio == 1.0 {
+                result = result.add(Self::variable().scale(*coefficient));
+            } else {
+                let factor = coefficient * ratio / (ratio - 1.0);
+                result = result.add(Self {
+                    polynomial: vec![-factor],
+                    geometric: vec![(factor, *ratio)],
+                });
+            }
+        }
+        result
+    }
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Bernoulli numbers `B_0..B_n` with `B_1 = +1/2`
+fn bernoulli_numbers(n: usize) -> Vec<f64> {
+    let mut numbers: Vec<f64> = Vec::with_capacity(n);
+    for m in 0..n {
+        if m == 0 {
+            numbers.push(1.0);
+        } else {
+            let sum: f64 = (0..m).map(|k| binomial(m + 1, k) * numbers[k]).sum();
+            numbers.push(-sum / (m + 1) as f64);
+        }
+    }
+    if numbers.len() > 1 {
+        numbers[1] = 0.5;
+    }
+    numbers
+}
+
+fn series(evaluate: &Evaluate, bindings: &mut HashMap<String, Series>) -> Result<Series, String> {
+    let mut binary = |node: &BinaryOperation| -> Result<(Series, Series), String> {
+        Ok((
+            series(node.left.as_ref(), bindings)?,
+            series(node.right.as_ref(), bindings)?,
+        ))
+    };
+    match &evaluate.node {
+        Node::Add(node) => {
+            let (left, right) = binary(node)?;
+            Ok(left.add(right))
+        }
+        Node::Sub(node) => {
+            let (left, right) = binary(node)?;
+            Ok(left.add(right.scale(-1.0)))
+        }
+        Node::Mul(node) => {
+            let (left, right) = binary(node)?;
+            left.mul(right)
+        }
+        Node::Div(node) => {
+            let (left, right) = binary(node)?;
+            match right.as_constant() {
+                Some(divisor) if divisor != 0.0 => Ok(left.scale(1.0 / divisor)),
+                _ => Err("Cannot sum a division by a non-constant or zero".to_string()),
+            }
+        }
+        Node::Pow(node) => {
+            let (left, right) = binary(node)?;
+            left.pow(right)
+        }
+        Node::Mod(node) | Node::Log(node) => {
+            let (left, right) = binary(node)?;
+            match (left.as_constant(), right.as_constant()) {
+                (Some(left), Some(right)) => Ok(Series::constant(match &evaluate.node {
+                    Node::Mod(_) => left % right,
+                    _ => left.log(right),
+                })),
+                _ => Err(format!("Cannot sum {evaluate}")),
+            }
+        }
+        Node::Number(node) => Ok(Series::constant(*node)),
+        Node::Variable(name) => match bindings.get(name) {
+            Some(value) => Ok(value.clone()),
+            None if name == INPUT => Ok(Series::variable()),
+            None => Err(format!("Unbound variable: {name}")),
+        },
+        Node::Let(node) => {
+            let value = series(node.value.as_ref(), bindings)?;
+            let shadowed = bindings.insert(node.name.clone(), value);
+            let result = series(node.body.as_ref(), bindings);
+            match shadowed {
+                Some(shadowed) => bindings.insert(node.name.clone(), shadowed),
+                None => bindings.remove(&node.name),
+            };
+            result
+        }
+        Node::Def(_) | Node::Call(_) => Err("Cannot sum function calls".to_string()),
+        Node::Rand(_) | Node::RandInt(_) | Node::Dice(_) => {
+            Err("Cannot sum random terms".to_string())
+        }
+    }
+}
+
+/// Add `coefficient * term` to `sum`, if the coefficient is not zero
+fn push_term(sum: Option<Evaluate>, coefficient: f64, term: Option<Evaluate>) -> Option<Evaluate> {
+    if coefficient == 0.0 {
+        return sum;
+    }
+    let scaled = |coefficient: f64| match term {
+        Some(term) if coefficient == 1.0 => term,
+        Some(term) => Evaluate::binary(Node::Mul, coefficient.into(), term),
+        None => coefficient.into(),
+    };
+    Some(match sum {
+        None => scaled(coefficient),
+        Some(sum) if coefficient < 0.0 => Evaluate::binary(Node::Sub, sum, scaled(-coefficient)),
+        Some(sum) => Evaluate::binary(Node::Add, sum, scaled(coefficient)),
+    })
+}
+
+fn to_evaluate(series: &Series) -> Evaluate {
+    let variable = || Node::Variable(INPUT.to_string()).into();
+    let mut sum = None;
+    for (i, c) in series.polynomial.iter().enumerate().rev() {
+        let term = match i {
+            0 => None,
+            1 => Some(variable()),
+            _ => Some(Evaluate::binary(Node::Pow, variable(), (i as f64).into())),
+        };
+        sum = push_term(sum, *c, term);
+    }
+    for (coefficient, ratio) in &series.geometric {
+        sum = push_term(
+            sum,
+            *coefficient,
+            Some(Evaluate::binary(Node::Pow, (*ratio).into(), variable())),
+        );
+    }
+    sum.unwrap_or_else(|| 0.0.into())
+}
+
+pub fn prefix_sum(evaluate: &Evaluate) -> Result<Evaluate, String> {
+    let series = series(evaluate, &mut HashMap::new())?;
+    Ok(to_evaluate(&series.prefix_sum()))
+}
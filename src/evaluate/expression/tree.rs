@@ -0,0 +1,284 @@
+//! Structured encoding of expressions
+//!
+//! Expressions serialize as their source string by default. This module
+//! encodes them as a tagged tree instead, for tools which build curves
+//! programmatically. Opt in per field with `#[serde(with = "...")]`.
+//!
+//! # Examples
+//!
+//! ```
+//! use flp_math::evaluate::expression::{tree, Evaluate};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Config {
+//!     #[serde(with = "tree")]
+//!     curve: Evaluate,
+//! }
+//!
+//! let config = Config {
+//!     curve: Evaluate::try_from_str("((x * 2) + 3)").unwrap(),
+//! };
+//! let json = serde_json::to_string(&config).unwrap();
+//! assert_eq!(
+//!     json,
+//!     r#"{"curve":{"op":"add","left":{"op":"mul","left":{"op":"variable","name":"x"},"right":{"op":"number","value":2.0}},"right":{"op":"number","value":3.0}}}"#
+//! );
+//! let config: Config = serde_json::from_str(&json).unwrap();
+//! assert_eq!(config.curve.to_string(), "((x * 2) + 3)");
+//! ```
+
+use nom::{
+    branch::alt,
+    combinator::{all_consuming, complete, map},
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    atom::{dice, identifier, last_number},
+    scope, BinaryOperation, DiceRoll, Evaluate, FunctionCall, FunctionDefinition, FunctionRegistry,
+    LetBinding, Node,
+};
+
+/// Tagged tree of an expression
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Tree {
+    Add {
+        left: Box<Tree>,
+        right: Box<Tree>,
+    },
+    Sub {
+        left: Box<Tree>,
+        right: Box<Tree>,
+    },
+    Mul {
+        left: Box<Tree>,
+        right: Box<Tree>,
+    },
+    Div {
+        left: Box<Tree>,
+        right: Box<Tree>,
+    },
+    Mod {
+        left: Box<Tree>,
+        right: Box<Tree>,
+    },
+    Pow {
+        left: Box<Tree>,
+        right: Box<Tree>,
+    },
+    Log {
+        left: Box<Tree>,
+        right: Box<Tree>,
+    },
+    Number {
+        value: f64,
+    },
+    Variable {
+        name: String,
+    },
+    Let {
+        name: String,
+        value: Box<Tree>,
+        body: Box<Tree>,
+    },
+    Def {
+        name: String,
+        params: Vec<String>,
+        value: Box<Tree>,
+        body: Box<Tree>,
+    },
+    Call {
+        name: String,
+        args: Vec<Tree>,
+    },
+    Rand {
+        low: Box<Tree>,
+        high: Box<Tree>,
+    },
+    RandInt {
+        low: Box<Tree>,
+        high: Box<Tree>,
+    },
+    Dice {
+        count: u32,
+        sides: u32,
+    },
+}
+
+fn boxed(evaluate: &Evaluate) -> Box<Tree> {
+    Box::new(evaluate.into())
+}
+
+impl From<&Evaluate> for Tree {
+    fn from(value: &Evaluate) -> Self {
+        match &value.node {
+            Node::Add(node) => Tree::Add {
+                left: boxed(&node.left),
+                right: boxed(&node.right),
+            },
+            Node::Sub(node) => Tree::Sub {
+                left: boxed(&node.left),
+                right: boxed(&node.right),
+            },
+            Node::Mul(node) => Tree::Mul {
+                left: boxed(&node.left),
+                right: boxed(&node.right),
+            },
+            Node::Div(node) => Tree::Div {
+                left: boxed(&node.left),
+                right: boxed(&node.right),
+            },
+            Node::Mod(node) => Tree::Mod {
+                left: boxed(&node.left),
+                right: boxed(&node.right),
+            },
+            Node::Pow(node) => Tree::Pow {
+                left: boxed(&node.left),
+                right: boxed(&node.right),
+            },
+            Node::Log(node) => Tree::Log {
+                left: boxed(&node.left),
+                right: boxed(&node.right),
+            },
+            Node::Number(node) => Tree::Number { value: *node },
+            Node::Variable(name) => Tree::Variable { name: name.clone() },
+            Node::Let(node) => Tree::Let {
+                name: node.name.clone(),
+                value: boxed(&node.value),
+                body: boxed(&node.body),
+            },
+            Node::Def(node) => Tree::Def {
+                name: node.name.clone(),
+                params: node.params.clone(),
+                value: boxed(&node.value),
+                body: boxed(&node.body),
+            },
+            Node::Call(node) => Tree::Call {
+                name: node.name.clone(),
+                args: node.args.iter().map(Into::into).collect(),
+            },
+            Node::Rand(node) => Tree::Rand {
+                low: boxed(&node.left),
+                high: boxed(&node.right),
+            },
+            Node::RandInt(node) => Tree::RandInt {
+                low: boxed(&node.left),
+                high: boxed(&node.right),
+            },
+            Node::Dice(node) => Tree::Dice {
+                count: node.count,
+                sides: node.sides,
+            },
+        }
+    }
+}
+
+fn binary(left: Tree, right: Tree) -> BinaryOperation {
+    BinaryOperation::new(unresolved(left), unresolved(right))
+}
+
+fn unresolved(tree: Tree) -> Evaluate {
+    let node = match tree {
+        Tree::Add { left, right } => Node::Add(binary(*left, *right)),
+        Tree::Sub { left, right } => Node::Sub(binary(*left, *right)),
+        Tree::Mul { left, right } => Node::Mul(binary(*left, *right)),
+        Tree::Div { left, right } => Node::Div(binary(*left, *right)),
+        Tree::Mod { left, right } => Node::Mod(binary(*left, *right)),
+        Tree::Pow { left, right } => Node::Pow(binary(*left, *right)),
+        Tree::Log { left, right } => Node::Log(binary(*left, *right)),
+        Tree::Number { value } => Node::Number(value),
+        Tree::Variable { name } => Node::Variable(name),
+        Tree::Let { name, value, body } => Node::Let(LetBinding {
+            name,
+            value: Box::new(unresolved(*value)),
+            body: Box::new(unresolved(*body)),
+        }),
+        Tree::Def {
+            name,
+            params,
+            value,
+            body,
+        } => Node::Def(FunctionDefinition {
+            name,
+            params,
+            value: Box::new(unresolved(*value)),
+            body: Box::new(unresolved(*body)),
+        }),
+        Tree::Call { name, args } => Node::Call(FunctionCall {
+            name,
+            args: args.into_iter().map(unresolved).collect(),
+            host: None,
+        }),
+        Tree::Rand { low, high } => Node::Rand(binary(*low, *high)),
+        Tree::RandInt { low, high } => Node::RandInt(binary(*low, *high)),
+        Tree::Dice { count, sides } => Node::Dice(DiceRoll { count, sides }),
+    };
+    node.into()
+}
+
+impl Tree {
+    /// Read a single token as a dice term, a number or a variable
+    pub(super) fn from_token(token: &str) -> Result<Self, String> {
+        all_consuming(alt((
+            map(complete(dice), |(count, sides)| Tree::Dice { count, sides }),
+            map(last_number, |value| Tree::Number { value }),
+            map(identifier, |name| Tree::Variable { name }),
+        )))(token)
+        .map(|(_, tree)| tree)
+        .map_err(|_| format!("Invalid token: {token}"))
+    }
+
+    /// Build the expression, calling the functions in `registry`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::expression::{tree::Tree, FunctionRegistry};
+    /// let mut registry = FunctionRegistry::new();
+    /// registry.allow_random();
+    /// let dice = Tree::Dice { count: 2, sides: 6 };
+    /// assert!(dice.try_into_evaluate_with(&registry).is_ok());
+    /// let dice = Tree::Dice { count: 2, sides: 0 };
+    /// assert!(dice.try_into_evaluate_with(&registry).is_err());
+    /// let variable = Tree::Variable { name: "inf".to_string() };
+    /// let tree = Tree::Let {
+    ///     name: "inf".to_string(),
+    ///     value: Box::new(Tree::Number { value: 1.0 }),
+    ///     body: Box::new(variable),
+    /// };
+    /// assert_eq!(tree.try_into_evaluate_with(&registry).unwrap_err(), "Invalid variable name: inf");
+    /// let tree = Tree::Variable { name: "a b".to_string() };
+    /// assert_eq!(tree.try_into_evaluate_with(&registry).unwrap_err(), "Invalid variable name: a b");
+    /// ```
+    pub fn try_into_evaluate_with(self, registry: &FunctionRegistry) -> Result<Evaluate, String> {
+        let mut evaluate = unresolved(self);
+        scope::resolve(&mut evaluate, &mut Vec::new(), registry)?;
+        Ok(evaluate)
+    }
+}
+
+impl TryFrom<Tree> for Evaluate {
+    type Error = String;
+
+    fn try_from(value: Tree) -> Result<Self, Self::Error> {
+        value.try_into_evaluate_with(&FunctionRegistry::default())
+    }
+}
+
+pub fn serialize<S>(evaluate: &Evaluate, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    Tree::from(evaluate).serialize(serializer)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Evaluate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Tree::deserialize(deserializer)?
+        .try_into()
+        .map_err(de::Error::custom)
+}
@@ -0,0 +1,278 @@
+use std::{f64::consts::E, fmt, hash::Hash};
+
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use super::{expression, value_map, Evaluate};
+
+/// Shape of a fitted curve
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Model {
+    /// `c0 + c1 * x + ... + cn * x ^ n` of the given degree `n`
+    Polynomial(usize),
+    /// `a * b ^ x`
+    Exponential,
+    /// `a * x ^ b`
+    Power,
+    /// `l / (1 + e ^ (k * (x0 - x)))`
+    Logistic,
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Model::Polynomial(degree) => write!(f, "polynomial of degree {degree}"),
+            Model::Exponential => f.write_str("exponential"),
+            Model::Power => f.write_str("power"),
+            Model::Logistic => f.write_str("logistic"),
+        }
+    }
+}
+
+/// A curve fitted to sample points by least squares
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fit {
+    pub model: Model,
+    pub expression: expression::Evaluate,
+    /// Coefficient of determination, 1 for a perfect fit
+    pub r_squared: f64,
+    /// Largest absolute difference between a sample and the curve
+    pub max_error: f64,
+}
+
+/// Sample points of a value map, ordered by input
+pub fn points<X, Y>(map: &value_map::Evaluate<X, Y>) -> Vec<(f64, f64)>
+where
+    X: Eq + Hash + ToPrimitive,
+    Y: ToPrimitive,
+{
+    let mut points: Vec<(f64, f64)> = map
+        .0
+        .iter()
+        .map(|(x, y)| {
+            (
+                x.to_f64().expect("Cannot convert X to f64"),
+                y.to_f64().expect("Cannot convert Y to f64"),
+            )
+        })
+        .collect();
+    points.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    points
+}
+
+/// Solve the square linear system `matrix * solution = vector`
+fn solve(mut matrix: Vec<Vec<f64>>, mut vector: Vec<f64>) -> Option<Vec<f64>> {
+    let n = vector.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| {
+            matrix[*a][column]
+                .abs()
+                .total_cmp(&matrix[*b][column].abs())
+        })?;
+        if matrix[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(column, pivot);
+        vector.swap(column, pivot);
+        let pivot_row = matrix[column].clone();
+        for row in column + 1..n {
+            let factor = matrix[row][column] / pivot_row[column];
+            for (cell, pivot) in matrix[row][column..].iter_mut().zip(&pivot_row[column..]) {
+                *cell -= factor * pivot;
+            }
+            vector[row] -= factor * vector[column];
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (vector[row] - sum) / matrix[row][row];
+    }
+    Some(solution)
+}
+
+/// Coefficients of the least squares polynomial of `degree`, the constant first
+fn polynomial(points: &[(f64, f64)], degree: usize) -> Option<Vec<f64>> {
+    let n = degree + 1;
+    let mut matrix = vec![vec![0.0; n]; n];
+    let mut vector = vec![0.0; n];
+    for (x, y) in points {
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell += x.powi((i + j) as i32);
+            }
+            vector[i] += y * x.powi(i as i32);
+        }
+    }
+    solve(matrix, vector)
+}
+
+/// Intercept and slope of the least squares line through the points
+fn line(points: impl Iterator<Item = (f64, f64)>) -> Option<(f64, f64)> {
+    let points: Vec<(f64, f64)> = points.collect();
+    polynomial(&points, 1).map(|line| (line[0], line[1]))
+}
+
+/// Log of every value, if they are all positive
+fn positive_log(values: impl Iterator<Item = f64>, name: &str) -> Result<Vec<f64>, String> {
+    values
+        .map(|value| {
+            if value > 0.0 {
+                Ok(value.ln())
+            } else {
+                Err(format!("Cannot fit with non-positive {name} {value}"))
+            }
+        })
+        .collect()
+}
+
+fn parse(source: String) -> Result<expression::Evaluate, String> {
+    expression::Evaluate::try_from_str(&source)
+}
+
+fn logistic(points: &[(f64, f64)]) -> Result<expression::Evaluate, String> {
+    positive_log(points.iter().map(|(_, y)| *y), "y")?;
+    let max = points.iter().map(|(_, y)| *y).fold(f64::MIN, f64::max);
+    // The capacity is searched, the rest follows from a line through ln(l / y - 1) = k * x0 - k * x
+    let candidate = |capacity: f64| {
+        let (intercept, slope) = line(points.iter().map(|(x, y)| (*x, (capacity / y - 1.0).ln())))?;
+        let k = -slope;
+        let x0 = intercept / k;
+        let source = format!("({capacity} / (1 + ({E} ^ ({k} * ({x0} - x)))))");
+        let evaluate = parse(source).ok()?;
+        let (r_squared, _) = statistics(&evaluate, points);
+        Some((r_squared, capacity, evaluate)).filter(|(r_squared, _, _)| r_squared.is_finite())
+    };
+    let best = |capacities: &mut dyn Iterator<Item = f64>| {
+        capacities
+            .filter_map(candidate)
+            .max_by(|(a, _, _), (b, _, _)| a.total_cmp(b))
+    };
+    let (_, coarse, _) = best(&mut (1..=400).map(|i| max * (1.0 + i as f64 / 100.0)))
+        .ok_or_else(|| String::from("Cannot fit a logistic curve"))?;
+    let (_, _, evaluate) = best(
+        &mut (-100..=100)
+            .map(|i| coarse + max * i as f64 / 10000.0)
+            .filter(|capacity| *capacity > max),
+    )
+    .ok_or_else(|| String::from("Cannot fit a logistic curve"))?;
+    Ok(evaluate)
+}
+
+/// R² and max error of `evaluate` at the points
+fn statistics(evaluate: &expression::Evaluate, points: &[(f64, f64)]) -> (f64, f64) {
+    let mean = points.iter().map(|(_, y)| y).sum::<f64>() / points.len() as f64;
+    let mut residual = 0.0;
+    let mut total = 0.0;
+    let mut max_error: f64 = 0.0;
+    for (x, y) in points {
+        let error = y - Evaluate::<f64, f64>::evaluate(evaluate, *x);
+        residual += error * error;
+        total += (y - mean) * (y - mean);
+        max_error = max_error.max(error.abs());
+    }
+    let r_squared = if total == 0.0 {
+        if residual == 0.0 {
+            1.0
+        } else {
+            0.0
+        }
+    } else {
+        1.0 - residual / total
+    };
+    (r_squared, max_error)
+}
+
+impl Model {
+    /// Fit this model to sample points by least squares
+    ///
+    /// Exponential and power models are fitted on the log of the values, which must be
+    /// positive, and so are the inputs of power models. Logistic models need positive values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use flp_math::evaluate::{fit::{self, Model}, value_map, Evaluate};
+    /// let table = value_map::Evaluate(HashMap::from([(1u8, 3u32), (2, 9), (3, 27), (4, 81)]));
+    /// let fit = Model::Exponential.fit(&fit::points(&table)).unwrap();
+    /// assert!(fit.r_squared > 0.999999 && fit.max_error < 1e-9);
+    /// assert_eq!(Evaluate::<f64, f64>::evaluate(&fit.expression, 5.0).round(), 243.0);
+    ///
+    /// let fit = Model::Polynomial(2).fit(&[(0.0, 1.0), (1.0, 3.0), (2.0, 7.0), (3.0, 13.0)]).unwrap();
+    /// assert_eq!(Evaluate::<f64, f64>::evaluate(&fit.expression, 4.0).round(), 21.0);
+    ///
+    /// assert_eq!(
+    ///     Model::Power.fit(&[(0.0, 1.0), (1.0, 2.0)]).unwrap_err(),
+    ///     "Cannot fit with non-positive x 0"
+    /// );
+    /// ```
+    pub fn fit(&self, points: &[(f64, f64)]) -> Result<Fit, String> {
+        if points.len() < 2 {
+            return Err(String::from("Cannot fit fewer than two points"));
+        }
+        let expression = match self {
+            Model::Polynomial(degree) => {
+                let coefficients = polynomial(points, *degree)
+                    .ok_or_else(|| format!("Not enough distinct inputs to fit a {self}"))?;
+                let source = coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| match i {
+                        0 => c.to_string(),
+                        1 => format!("({c} * x)"),
+                        _ => format!("({c} * (x ^ {i}))"),
+                    })
+                    .reduce(|sum, term| format!("({sum} + {term})"))
+                    .unwrap_or_default();
+                parse(source)?
+            }
+            Model::Exponential => {
+                let ys = positive_log(points.iter().map(|(_, y)| *y), "y")?;
+                let (a, b) = line(points.iter().map(|(x, _)| *x).zip(ys))
+                    .ok_or_else(|| format!("Not enough distinct inputs to fit a {self}"))?;
+                parse(format!("({} * ({} ^ x))", a.exp(), b.exp()))?
+            }
+            Model::Power => {
+                let xs = positive_log(points.iter().map(|(x, _)| *x), "x")?;
+                let ys = positive_log(points.iter().map(|(_, y)| *y), "y")?;
+                let (a, b) = line(xs.into_iter().zip(ys))
+                    .ok_or_else(|| format!("Not enough distinct inputs to fit a {self}"))?;
+                parse(format!("({} * (x ^ {b}))", a.exp()))?
+            }
+            Model::Logistic => logistic(points)?,
+        };
+        let (r_squared, max_error) = statistics(&expression, points);
+        Ok(Fit {
+            model: *self,
+            expression,
+            r_squared,
+            max_error,
+        })
+    }
+}
+
+/// Fit every model which applies to the points, best R² first
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::fit::{self, Model};
+/// let points: Vec<(f64, f64)> = (1..=10).map(|x| (x as f64, 5.0 * (x as f64).powf(1.5))).collect();
+/// let fits = fit::rank(
+///     &[Model::Polynomial(1), Model::Exponential, Model::Power, Model::Logistic],
+///     &points,
+/// );
+/// assert_eq!(fits.len(), 4);
+/// assert_eq!(fits[0].model, Model::Power);
+/// assert!(fits.windows(2).all(|pair| pair[0].r_squared >= pair[1].r_squared));
+/// ```
+pub fn rank(models: &[Model], points: &[(f64, f64)]) -> Vec<Fit> {
+    let mut fits: Vec<Fit> = models
+        .iter()
+        .filter_map(|model| model.fit(points).ok())
+        .collect();
+    fits.sort_by(|a, b| b.r_squared.total_cmp(&a.r_squared));
+    fits
+}
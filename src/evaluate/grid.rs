@@ -0,0 +1,326 @@
+use std::str::FromStr;
+
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use super::delimited;
+
+/// How to look up inputs in a grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lookup {
+    /// Both inputs must be keys
+    #[default]
+    Exact,
+    /// Take the value of the closest keys, the later key on ties
+    Nearest,
+    /// Interpolate linearly along both inputs between the surrounding keys
+    Bilinear,
+}
+
+/// What to do with an input outside the keys
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Edge<Y> {
+    /// Panic with out of bound
+    #[default]
+    Panic,
+    /// Use the first or the last key
+    Clamp,
+    /// Continue the line through the first or the last two keys, like `Clamp` unless bilinear
+    Extrapolate,
+    /// A fixed value
+    Default(Y),
+}
+
+fn parse<T>(line_number: usize, cell: &str) -> Result<T, String>
+where
+    T: FromStr,
+{
+    cell.parse()
+        .map_err(|_| format!("line {line_number}: cannot parse `{cell}`"))
+}
+
+/// Where an input falls on an axis: the keys around it and how far it is from the first one
+struct Position {
+    lower: usize,
+    upper: usize,
+    t: f64,
+}
+
+/// Evaluate with a matrix of values keyed by rows of `X1` and columns of `X2`.
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{delimited, grid::{self, Edge, Lookup}, Evaluate};
+/// let csv = "level\\tier,1,2,3\n1,10,20,40\n11,30,60,120\n";
+/// let grid = grid::Evaluate::<u8, u8, u32>::from_csv(csv, delimited::CSV, Lookup::Bilinear, Edge::Clamp)
+///     .unwrap();
+/// assert_eq!(grid.evaluate((11, 2)), 60);
+/// assert_eq!(grid.evaluate((6, 2)), 40);
+/// assert_eq!(grid.evaluate((50, 9)), 120);
+///
+/// let json = r#"{
+///     "rows": [1, 11],
+///     "columns": [1, 2],
+///     "values": [[10, 20], [30, 60]],
+///     "lookup": "nearest",
+///     "edge": {"default": 0}
+/// }"#;
+/// let grid: grid::Evaluate<u8, u8, u32> = serde_json::from_str(json).unwrap();
+/// assert_eq!(grid.evaluate((7, 1)), 30);
+/// assert_eq!(grid.evaluate((12, 1)), 0);
+///
+/// let ragged = grid::Evaluate::<u8, u8, u32>::from_csv("x,1,2\n1,10\n", delimited::CSV, Lookup::Exact, Edge::Panic);
+/// assert_eq!(ragged.err(), Some(String::from("line 2: expected 3 columns, found 2")));
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Evaluate<X1, X2, Y> {
+    rows: Vec<X1>,
+    columns: Vec<X2>,
+    values: Vec<Vec<Y>>,
+    lookup: Lookup,
+    edge: Edge<Y>,
+}
+
+impl<X1, X2, Y> Evaluate<X1, X2, Y>
+where
+    X1: Clone + Copy + PartialOrd + ToPrimitive,
+    X2: Clone + Copy + PartialOrd + ToPrimitive,
+{
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// - `rows` - keys of the first input, increasing
+    /// - `columns` - keys of the second input, increasing
+    /// - `values` - one row of values per key in `rows`, each with one value per key in `columns`
+    /// - `lookup` - how to look up inputs
+    /// - `edge` - what to do with inputs outside the keys
+    ///
+    /// # Errors
+    ///
+    /// If the keys are empty or not increasing, or `values` does not match them
+    pub fn new(
+        rows: Vec<X1>,
+        columns: Vec<X2>,
+        values: Vec<Vec<Y>>,
+        lookup: Lookup,
+        edge: Edge<Y>,
+    ) -> Result<Self, String> {
+        if rows.is_empty() || columns.is_empty() {
+            return Err(String::from("Grid needs at least one row and one column"));
+        }
+        if rows.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(String::from("Grid rows are not increasing"));
+        }
+        if columns.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(String::from("Grid columns are not increasing"));
+        }
+        if values.len() != rows.len() {
+            return Err(format!(
+                "Grid has {} rows of values for {} rows",
+                values.len(),
+                rows.len()
+            ));
+        }
+        if let Some(row) = values.iter().position(|row| row.len() != columns.len()) {
+            return Err(format!(
+                "Grid row {row} has {} values for {} columns",
+                values[row].len(),
+                columns.len()
+            ));
+        }
+        Ok(Self {
+            rows,
+            columns,
+            values,
+            lookup,
+            edge,
+        })
+    }
+
+    pub fn rows(&self) -> &[X1] {
+        &self.rows
+    }
+
+    pub fn columns(&self) -> &[X2] {
+        &self.columns
+    }
+
+    pub fn values(&self) -> &[Vec<Y>] {
+        &self.values
+    }
+
+    /// Read a grid from delimited text
+    ///
+    /// The first line holds a corner cell followed by the column keys, and every other line a
+    /// row key followed by the values of that row.
+    ///
+    /// # Errors
+    ///
+    /// With the line number if a line does not have one cell per column or a cell does not parse
+    pub fn from_csv(s: &str, delimiter: char, lookup: Lookup, edge: Edge<Y>) -> Result<Self, String>
+    where
+        X1: FromStr,
+        X2: FromStr,
+        Y: FromStr,
+    {
+        let mut records = delimited::records(s, delimiter)?.into_iter();
+        let (line_number, header) = records.next().ok_or("Grid needs a header line")?;
+        let columns = header
+            .iter()
+            .skip(1)
+            .map(|cell| parse(line_number, &cell.text))
+            .collect::<Result<Vec<X2>, String>>()?;
+        let mut rows = Vec::new();
+        let mut values = Vec::new();
+        for (line_number, cells) in records {
+            if cells.len() != columns.len() + 1 {
+                return Err(format!(
+                    "line {line_number}: expected {} columns, found {}",
+                    columns.len() + 1,
+                    cells.len()
+                ));
+            }
+            rows.push(parse(line_number, &cells[0].text)?);
+            values.push(
+                cells[1..]
+                    .iter()
+                    .map(|cell| parse(line_number, &cell.text))
+                    .collect::<Result<Vec<Y>, String>>()?,
+            );
+        }
+        Self::new(rows, columns, values, lookup, edge)
+    }
+
+    /// Position of `key` on `axis`, or `None` for the default value
+    fn locate<K>(&self, axis: &[K], key: K) -> Option<Position>
+    where
+        K: Clone + Copy + PartialOrd + ToPrimitive,
+    {
+        let to_f64 = |key: &K| key.to_f64().expect("Cannot convert X to f64");
+        let last = axis.len() - 1;
+        let outside = |lower: usize, upper: usize| match self.edge {
+            Edge::Panic => panic!("Out of bound: Grid"),
+            Edge::Extrapolate if self.lookup == Lookup::Bilinear && last > 0 => {
+                let (from, to) = (to_f64(&axis[lower]), to_f64(&axis[upper]));
+                Some(Position {
+                    lower,
+                    upper,
+                    t: (to_f64(&key) - from) / (to - from),
+                })
+            }
+            Edge::Clamp | Edge::Extrapolate => {
+                let index = if key < axis[0] { 0 } else { last };
+                Some(Position {
+                    lower: index,
+                    upper: index,
+                    t: 0.0,
+                })
+            }
+            Edge::Default(_) => None,
+        };
+        if key < axis[0] {
+            return outside(0, 1.min(last));
+        }
+        if key > axis[last] {
+            return outside(last.saturating_sub(1), last);
+        }
+        let upper = axis.partition_point(|k| *k < key);
+        if axis[upper] == key {
+            return Some(Position {
+                lower: upper,
+                upper,
+                t: 0.0,
+            });
+        }
+        let (from, to) = (to_f64(&axis[upper - 1]), to_f64(&axis[upper]));
+        Some(Position {
+            lower: upper - 1,
+            upper,
+            t: (to_f64(&key) - from) / (to - from),
+        })
+    }
+}
+
+impl<'de, X1, X2, Y> Deserialize<'de> for Evaluate<X1, X2, Y>
+where
+    X1: Clone + Copy + PartialOrd + ToPrimitive + Deserialize<'de>,
+    X2: Clone + Copy + PartialOrd + ToPrimitive + Deserialize<'de>,
+    Y: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Grid<X1, X2, Y> {
+            rows: Vec<X1>,
+            columns: Vec<X2>,
+            values: Vec<Vec<Y>>,
+            #[serde(default)]
+            lookup: Lookup,
+            #[serde(default = "Edge::default")]
+            edge: Edge<Y>,
+        }
+        let grid = Grid::deserialize(deserializer)?;
+        Self::new(grid.rows, grid.columns, grid.values, grid.lookup, grid.edge)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<X1, X2, Y> super::Evaluate<(X1, X2), Y> for Evaluate<X1, X2, Y>
+where
+    X1: Clone + Copy + PartialOrd + ToPrimitive,
+    X2: Clone + Copy + PartialOrd + ToPrimitive,
+    Y: Clone + Copy + ToPrimitive + FromPrimitive,
+{
+    fn evaluate(&self, (x1, x2): (X1, X2)) -> Y {
+        let (row, column) = match (self.locate(&self.rows, x1), self.locate(&self.columns, x2)) {
+            (Some(row), Some(column)) => (row, column),
+            _ => match self.edge {
+                Edge::Default(value) => return value,
+                _ => unreachable!(),
+            },
+        };
+        match self.lookup {
+            Lookup::Exact => {
+                if row.lower != row.upper || column.lower != column.upper {
+                    panic!("Missing key: Grid");
+                }
+                self.values[row.lower][column.lower]
+            }
+            Lookup::Nearest => {
+                let nearest = |position: &Position| {
+                    if position.t < 0.5 {
+                        position.lower
+                    } else {
+                        position.upper
+                    }
+                };
+                self.values[nearest(&row)][nearest(&column)]
+            }
+            Lookup::Bilinear => {
+                let value = |row: usize, column: usize| {
+                    self.values[row][column]
+                        .to_f64()
+                        .expect("Cannot convert Y to f64")
+                };
+                let lerp = |from: f64, to: f64, t: f64| from + (to - from) * t;
+                let lower = lerp(
+                    value(row.lower, column.lower),
+                    value(row.lower, column.upper),
+                    column.t,
+                );
+                let upper = lerp(
+                    value(row.upper, column.lower),
+                    value(row.upper, column.upper),
+                    column.t,
+                );
+                Y::from_f64(lerp(lower, upper, row.t)).expect("Cannot convert f64 to Y")
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// How to fill in the inputs between two keys
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Hold the value of the previous key
+    #[default]
+    Step,
+    /// Take the value of the closest key, the later key on ties
+    Nearest,
+    /// Draw a straight line between the values of the surrounding keys
+    Linear,
+}
+
+/// Evaluate with an ordered x-y map which only lists some inputs.
+///
+/// Inputs before the first key or after the last key are out of bound.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use flp_math::evaluate::{ordered_map::{Evaluate as OrderedMap, Interpolation}, Evaluate};
+/// let milestones = BTreeMap::from([(1u8, 100u32), (10, 1000), (25, 4000), (50, 10000)]);
+/// let step = OrderedMap::new(milestones.clone(), Interpolation::Step);
+/// assert_eq!(step.evaluate(24), 1000);
+/// let nearest = OrderedMap::new(milestones.clone(), Interpolation::Nearest);
+/// assert_eq!(nearest.evaluate(24), 4000);
+/// let linear = OrderedMap::new(milestones, Interpolation::Linear);
+/// assert_eq!(linear.evaluate(5), 500);
+/// assert_eq!(linear.evaluate(50), 10000);
+///
+/// let ron = "(values: {1: 10, 3: 30}, interpolation: linear)";
+/// let map: OrderedMap<u8, u16> = ron::from_str(ron).unwrap();
+/// assert_eq!(map.evaluate(2), 20);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Evaluate<X, Y>
+where
+    X: Ord,
+{
+    values: BTreeMap<X, Y>,
+    #[serde(default)]
+    interpolation: Interpolation,
+}
+
+impl<X, Y> Evaluate<X, Y>
+where
+    X: Clone + Copy + Ord,
+{
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// - `values` - values of the listed inputs
+    /// - `interpolation` - how to fill in the inputs between them
+    pub fn new(values: BTreeMap<X, Y>, interpolation: Interpolation) -> Self {
+        Self {
+            values,
+            interpolation,
+        }
+    }
+
+    pub fn values(&self) -> &BTreeMap<X, Y> {
+        &self.values
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+}
+
+impl<X, Y> super::Evaluate<X, Y> for Evaluate<X, Y>
+where
+    X: Clone + Copy + Ord + ToPrimitive,
+    Y: Clone + Copy + ToPrimitive + FromPrimitive,
+{
+    fn evaluate(&self, x: X) -> Y {
+        let (before, after) = match (
+            self.values.range(..=x).next_back(),
+            self.values.range(x..).next(),
+        ) {
+            (Some(before), Some(after)) => (before, after),
+            _ => panic!("Out of bound: OrderedMap"),
+        };
+        if before.0 == after.0 {
+            return *before.1;
+        }
+        let to_f64 = |n: &X| n.to_f64().expect("Cannot convert X to f64");
+        match self.interpolation {
+            Interpolation::Step => *before.1,
+            Interpolation::Nearest => {
+                if to_f64(&x) - to_f64(before.0) < to_f64(after.0) - to_f64(&x) {
+                    *before.1
+                } else {
+                    *after.1
+                }
+            }
+            Interpolation::Linear => {
+                let from = before.1.to_f64().expect("Cannot convert Y to f64");
+                let to = after.1.to_f64().expect("Cannot convert Y to f64");
+                let t = (to_f64(&x) - to_f64(before.0)) / (to_f64(after.0) - to_f64(before.0));
+                Y::from_f64(from + (to - from) * t).expect("Cannot convert f64 to Y")
+            }
+        }
+    }
+}
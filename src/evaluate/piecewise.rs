@@ -0,0 +1,185 @@
+use std::{fmt::Display, marker::PhantomData};
+
+use num_traits::{CheckedAdd, CheckedRem, CheckedSub, FromPrimitive, ToPrimitive};
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::bound::Bound;
+
+/// Evaluate with a different evaluation on each segment of the domain.
+///
+/// # Generic
+///
+/// - `X` - from type
+/// - `Y` - to type
+/// - `E` - evaluation of the segments
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::{bound::Bound, evaluate::{expression, piecewise, table, Evaluate}};
+/// let early = expression::Evaluate::try_from_str("(x * 10)").unwrap();
+/// let middle = table::Evaluate::<u8, u32>::precompute(&early, 21, 60);
+/// let late = expression::Evaluate::try_from_str("((x * 20) - 600)").unwrap();
+/// let curve = piecewise::Evaluate::<u8, u32>::new(
+///     vec![
+///         (Bound::new(61, 99, false), Box::new(late)),
+///         (Bound::new(1, 20, false), Box::new(early)),
+///         (Bound::new(21, 60, false), Box::new(middle)),
+///     ],
+///     Some(20.0),
+/// )
+/// .unwrap();
+/// assert_eq!(curve.evaluate(20), 200);
+/// assert_eq!(curve.evaluate(40), 400);
+/// assert_eq!(curve.evaluate(80), 1000);
+/// assert_eq!(curve.try_evaluate(0), Err(String::from("Input 0 is not covered by any segment")));
+///
+/// let json = r#"{
+///     "segments": [[{"min": 1, "max": 20}, "(x * 10)"], [{"min": 21, "max": 99}, "(x * 20)"]],
+///     "tolerance": 50
+/// }"#;
+/// let jump = serde_json::from_str::<piecewise::Evaluate<u8, u32, expression::Evaluate>>(json);
+/// assert!(jump
+///     .err()
+///     .unwrap()
+///     .to_string()
+///     .starts_with("Discontinuity of 220 between segments 1..=20 and 21..=99"));
+///
+/// let json = r#"{"segments": [[{"min": 20, "max": 1}, "(x)"]]}"#;
+/// let inverted = serde_json::from_str::<piecewise::Evaluate<u8, u32, expression::Evaluate>>(json);
+/// assert!(inverted.err().unwrap().to_string().starts_with("Invalid segment 20..=1"));
+/// ```
+#[derive(Serialize)]
+pub struct Evaluate<X, Y, E = Box<dyn super::Evaluate<X, Y>>> {
+    #[serde(skip)]
+    y: PhantomData<Y>,
+    segments: Vec<(Bound<X>, E)>,
+    tolerance: Option<f64>,
+}
+
+impl<X, Y, E> Evaluate<X, Y, E>
+where
+    X: Clone + Copy + Display + PartialOrd + CheckedAdd + CheckedSub + CheckedRem + FromPrimitive,
+    Y: ToPrimitive,
+    E: super::Evaluate<X, Y>,
+{
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// - `segments` - domain segments with their evaluations, in any order, whose `wrap` is ignored
+    /// - `tolerance` - if given, the largest allowed difference between the last value of a
+    ///   segment and the first value of the next one
+    ///
+    /// # Errors
+    ///
+    /// If a segment ends before it starts, two segments overlap, or the values jump by more than
+    /// `tolerance` where segments join
+    pub fn new(mut segments: Vec<(Bound<X>, E)>, tolerance: Option<f64>) -> Result<Self, String> {
+        if let Some((bound, _)) = segments.iter().find(|(bound, _)| bound.min > bound.max) {
+            return Err(format!("Invalid segment {}..={}", bound.min, bound.max));
+        }
+        segments.sort_by(|(a, _), (b, _)| {
+            a.min
+                .partial_cmp(&b.min)
+                .expect("Cannot compare segment bounds")
+        });
+        let one = X::from_u8(1).unwrap();
+        for pair in segments.windows(2) {
+            let ((before, left), (after, right)) = (&pair[0], &pair[1]);
+            let range = format!(
+                "{}..={} and {}..={}",
+                before.min, before.max, after.min, after.max
+            );
+            if after.min <= before.max {
+                return Err(format!("Overlap between segments {range}"));
+            }
+            if let Some(tolerance) = tolerance {
+                if before.max.checked_add(&one) != Some(after.min) {
+                    continue;
+                }
+                let left = left
+                    .evaluate(before.max)
+                    .to_f64()
+                    .expect("Cannot convert Y to f64");
+                let right = right
+                    .evaluate(after.min)
+                    .to_f64()
+                    .expect("Cannot convert Y to f64");
+                if (right - left).abs() > tolerance {
+                    return Err(format!(
+                        "Discontinuity of {} between segments {range}",
+                        (right - left).abs()
+                    ));
+                }
+            }
+        }
+        Ok(Self {
+            y: PhantomData,
+            segments,
+            tolerance,
+        })
+    }
+
+    /// Domain segments with their evaluations, in order
+    pub fn segments(&self) -> &[(Bound<X>, E)] {
+        &self.segments
+    }
+
+    /// Evaluate `x` with the segment which covers it
+    ///
+    /// # Errors
+    ///
+    /// If no segment covers `x`
+    pub fn try_evaluate(&self, x: X) -> Result<Y, String> {
+        let index = self.segments.partition_point(|(bound, _)| bound.max < x);
+        match self.segments.get(index) {
+            Some((bound, evaluate)) if bound.min <= x => Ok(evaluate.evaluate(x)),
+            _ => Err(format!("Input {x} is not covered by any segment")),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Segments<X, E> {
+    segments: Vec<(Bound<X>, E)>,
+    #[serde(default)]
+    tolerance: Option<f64>,
+}
+
+impl<'de, X, Y, E> Deserialize<'de> for Evaluate<X, Y, E>
+where
+    X: Clone
+        + Copy
+        + Display
+        + PartialOrd
+        + CheckedAdd
+        + CheckedSub
+        + CheckedRem
+        + FromPrimitive
+        + Deserialize<'de>,
+    Y: ToPrimitive,
+    E: super::Evaluate<X, Y> + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Segments {
+            segments,
+            tolerance,
+        } = Segments::deserialize(deserializer)?;
+        Self::new(segments, tolerance).map_err(de::Error::custom)
+    }
+}
+
+impl<X, Y, E> super::Evaluate<X, Y> for Evaluate<X, Y, E>
+where
+    X: Clone + Copy + Display + PartialOrd + CheckedAdd + CheckedSub + CheckedRem + FromPrimitive,
+    Y: ToPrimitive,
+    E: super::Evaluate<X, Y>,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.try_evaluate(x).expect("Out of bound: Piecewise")
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+use super::table;
+
+/// A stat whose value differs between two inputs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change<Y> {
+    pub name: String,
+    pub before: Y,
+    pub after: Y,
+}
+
+/// Evaluate every named stat with the same input, to a map of stat values.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use flp_math::evaluate::{any, stat_sheet::{self, Change}, Evaluate};
+/// let json = r#"{
+///     "hp": {"type": "expression", "source": "((x * 20) + 100)"},
+///     "mp": {"type": "bracket", "brackets": [[{"min": 1, "max": 9}, 10], [{"min": 10, "max": 99}, 30]]},
+///     "attack": {"type": "expression", "source": "(x * 3)"}
+/// }"#;
+/// let sheet: stat_sheet::Evaluate<any::Evaluate<u8, u32>> = serde_json::from_str(json).unwrap();
+/// let stats: BTreeMap<String, u32> = sheet.evaluate(10);
+/// assert_eq!(stats["hp"], 300);
+/// assert_eq!(stats["mp"], 30);
+///
+/// let dense = sheet.precompute::<u8, u32>(1, 99);
+/// assert_eq!(dense.evaluate(10), stats);
+/// assert_eq!(
+///     dense.changes::<u8, u32>(4, 5).into_iter().map(|change| change.name).collect::<Vec<_>>(),
+///     vec!["attack", "hp"]
+/// );
+/// assert_eq!(
+///     sheet.changes::<u8, u32>(9, 10)[2],
+///     Change { name: String::from("mp"), before: 10, after: 30 }
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Evaluate<E> {
+    stats: BTreeMap<String, E>,
+}
+
+impl<E> Default for Evaluate<E> {
+    fn default() -> Self {
+        Self {
+            stats: BTreeMap::new(),
+        }
+    }
+}
+
+impl<E> Evaluate<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a stat, replacing the one with the same name
+    pub fn insert(&mut self, name: &str, evaluate: E) -> &mut Self {
+        self.stats.insert(name.to_string(), evaluate);
+        self
+    }
+
+    pub fn stats(&self) -> &BTreeMap<String, E> {
+        &self.stats
+    }
+
+    /// Value of a single stat
+    pub fn stat<X, Y>(&self, name: &str, x: X) -> Option<Y>
+    where
+        E: super::Evaluate<X, Y>,
+    {
+        self.stats.get(name).map(|evaluate| evaluate.evaluate(x))
+    }
+
+    /// Evaluate every stat once for every input in `min..=max`
+    ///
+    /// # Panic
+    ///
+    /// If `min` is larger than `max`
+    pub fn precompute<X, Y>(&self, min: X, max: X) -> Evaluate<table::Evaluate<X, Y>>
+    where
+        X: Clone + Copy + PartialOrd + ToPrimitive + FromPrimitive,
+        E: super::Evaluate<X, Y>,
+    {
+        Evaluate {
+            stats: self
+                .stats
+                .iter()
+                .map(|(name, evaluate)| {
+                    (
+                        name.clone(),
+                        table::Evaluate::precompute(evaluate, min, max),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Stats whose values differ between `before` and `after`, ordered by name
+    pub fn changes<X, Y>(&self, before: X, after: X) -> Vec<Change<Y>>
+    where
+        X: Clone + Copy,
+        Y: PartialEq,
+        E: super::Evaluate<X, Y>,
+    {
+        self.stats
+            .iter()
+            .filter_map(|(name, evaluate)| {
+                let (before, after) = (evaluate.evaluate(before), evaluate.evaluate(after));
+                (before != after).then(|| Change {
+                    name: name.clone(),
+                    before,
+                    after,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<X, Y, E> super::Evaluate<X, BTreeMap<String, Y>> for Evaluate<E>
+where
+    X: Clone + Copy,
+    E: super::Evaluate<X, Y>,
+{
+    fn evaluate(&self, x: X) -> BTreeMap<String, Y> {
+        self.stats
+            .iter()
+            .map(|(name, evaluate)| (name.clone(), evaluate.evaluate(x)))
+            .collect()
+    }
+}
@@ -0,0 +1,90 @@
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// Evaluate with values precomputed over a contiguous integer domain.
+///
+/// # Examples
+///
+/// ```
+/// use flp_math::evaluate::{expression, table, Evaluate};
+/// let curve = expression::Evaluate::try_from_str("((x ^ 2) * 100)").unwrap();
+/// let table = table::Evaluate::<u8, u32>::precompute(&curve, 1, 99);
+/// assert_eq!(table.evaluate(50), 250000);
+/// assert_eq!(table.len(), 99);
+///
+/// let signed = table::Evaluate::<i8, i32>::precompute(&curve, -100, 100);
+/// assert_eq!(signed.evaluate(100), 1000000);
+/// assert_eq!(signed.len(), 201);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Evaluate<X, Y> {
+    min: X,
+    values: Vec<Y>,
+}
+
+impl<X, Y> Evaluate<X, Y>
+where
+    X: Clone + Copy + PartialOrd + ToPrimitive + FromPrimitive,
+{
+    /// Constructor
+    ///
+    /// # Arguments
+    ///
+    /// - `min` - the input of the first value
+    /// - `values` - values of consecutive inputs starting at `min`
+    pub fn new(min: X, values: Vec<Y>) -> Self {
+        Self { min, values }
+    }
+
+    /// Evaluate `evaluate` once for every input in `min..=max`
+    ///
+    /// # Panic
+    ///
+    /// If `min` is larger than `max`
+    pub fn precompute<E>(evaluate: &E, min: X, max: X) -> Self
+    where
+        E: super::Evaluate<X, Y>,
+    {
+        if min > max {
+            panic!("Invalid table domain");
+        }
+        let to_i128 = |x: X| x.to_i128().expect("Cannot convert X to i128");
+        let values = (to_i128(min)..=to_i128(max))
+            .map(|x| evaluate.evaluate(X::from_i128(x).expect("Cannot convert i128 to X")))
+            .collect();
+        Self { min, values }
+    }
+
+    pub fn min(&self) -> X {
+        self.min
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> &[Y] {
+        &self.values
+    }
+
+    fn index(&self, x: X) -> Option<usize> {
+        x.to_i128()?
+            .checked_sub(self.min.to_i128()?)?
+            .to_usize()
+            .filter(|index| *index < self.values.len())
+    }
+}
+
+impl<X, Y> super::Evaluate<X, Y> for Evaluate<X, Y>
+where
+    X: Clone + Copy + PartialOrd + ToPrimitive + FromPrimitive,
+    Y: Clone + Copy,
+{
+    fn evaluate(&self, x: X) -> Y {
+        self.values[self.index(x).expect("Out of bound: Table")]
+    }
+}
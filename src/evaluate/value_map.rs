@@ -1,6 +1,9 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{collections::HashMap, hash::Hash, iter};
 
-use serde::{Deserialize, Serialize};
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::expression;
 
 /// Evaluate with x-y map.
 #[derive(Deserialize, Serialize)]
@@ -8,6 +11,52 @@ pub struct Evaluate<X, Y>(pub HashMap<X, Y>)
 where
     X: PartialEq + Eq + Hash;
 
+impl<X, Y> Evaluate<X, Y>
+where
+    X: Clone + Copy + PartialEq + Eq + Hash,
+{
+    /// Evaluate `evaluate` once for every input in `domain`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use flp_math::evaluate::{expression, value_map, Evaluate};
+    /// let curve = expression::Evaluate::try_from_str("(x * 10)").unwrap();
+    /// let map = value_map::Evaluate::<u8, u16>::precompute(&curve, [1, 5, 10]);
+    /// assert_eq!(map.evaluate(5), 50);
+    /// assert_eq!(map.0.len(), 3);
+    /// ```
+    pub fn precompute<E>(evaluate: &E, domain: impl IntoIterator<Item = X>) -> Self
+    where
+        E: super::Evaluate<X, Y>,
+    {
+        Self(
+            domain
+                .into_iter()
+                .map(|x| (x, evaluate.evaluate(x)))
+                .collect(),
+        )
+    }
+
+    /// Use `fallback` for inputs which are not in the map instead of panicking
+    pub fn with_fallback<E>(self, fallback: Fallback<Y, E>) -> WithFallback<X, Y, E>
+    where
+        X: ToPrimitive,
+    {
+        let mut keys: Vec<(f64, X)> = self
+            .0
+            .keys()
+            .map(|key| (key.to_f64().expect("Cannot convert X to f64"), *key))
+            .collect();
+        keys.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        WithFallback {
+            values: self,
+            fallback,
+            keys,
+        }
+    }
+}
+
 impl<X, Y> super::Evaluate<X, Y> for Evaluate<X, Y>
 where
     X: PartialEq + Eq + Hash,
@@ -17,3 +66,180 @@ where
         *self.0.get(&x).expect("Out of bound: ValueMap")
     }
 }
+
+/// What to evaluate to when an input is not in the map
+///
+/// # Generic
+///
+/// - `Y` - to type
+/// - `E` - secondary evaluation to delegate to
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fallback<Y, E = expression::Evaluate> {
+    /// Value of the closest input, the smaller one on ties
+    Clamp,
+    /// Line through the values of the inputs around the missing one, or of the two smallest or
+    /// two largest inputs beyond them. Panics where the line leaves the range of `Y`, such as
+    /// below zero for unsigned types
+    Extrapolate,
+    /// Repeat the inputs in order: the distance of an input from the smallest one, modulo the
+    /// number of inputs, is the index of the input to use
+    Cycle,
+    /// A fixed value
+    Default(Y),
+    /// Evaluate with a secondary evaluation
+    Delegate(E),
+}
+
+/// Evaluate with x-y map, falling back for inputs which are not in the map.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use flp_math::evaluate::{expression, value_map, Evaluate};
+/// use value_map::{Fallback, WithFallback};
+/// let map = || value_map::Evaluate(HashMap::from([(1u8, 10u16), (2, 20), (3, 40)]));
+///
+/// let clamp: WithFallback<u8, u16> = map().with_fallback(Fallback::Clamp);
+/// assert_eq!(clamp.evaluate(9), 40);
+/// let extrapolate: WithFallback<u8, u16> = map().with_fallback(Fallback::Extrapolate);
+/// assert_eq!(extrapolate.evaluate(5), 80);
+/// let gap = value_map::Evaluate(HashMap::from([(1u8, 10u16), (2, 20), (10, 100)]));
+/// let extrapolate: WithFallback<u8, u16> = gap.with_fallback(Fallback::Extrapolate);
+/// assert_eq!(extrapolate.evaluate(6), 60);
+/// assert_eq!(extrapolate.evaluate(0), 0);
+/// let bend = value_map::Evaluate(HashMap::from([(1u8, 10u16), (2, 20), (10, 40)]));
+/// let extrapolate: WithFallback<u8, u16> = bend.with_fallback(Fallback::Extrapolate);
+/// assert_eq!(extrapolate.evaluate(4), 25);
+/// let falling = value_map::Evaluate(HashMap::from([(5u8, 50i16), (6, 40)]));
+/// let extrapolate: WithFallback<u8, i16> = falling.with_fallback(Fallback::Extrapolate);
+/// assert_eq!(extrapolate.evaluate(20), -100);
+/// let cycle: WithFallback<u8, u16> = map().with_fallback(Fallback::Cycle);
+/// assert_eq!(cycle.evaluate(5), 20);
+/// let sparse = value_map::Evaluate(HashMap::from([(1u8, 10u16), (10, 100)]));
+/// let cycle: WithFallback<u8, u16> = sparse.with_fallback(Fallback::Cycle);
+/// assert_eq!(cycle.evaluate(12), 100);
+/// let default: WithFallback<u8, u16> = map().with_fallback(Fallback::Default(0));
+/// assert_eq!(default.evaluate(0), 0);
+///
+/// let json = r#"{"values": {"1": 10, "2": 20}, "fallback": {"delegate": "(x * 100)"}}"#;
+/// let delegate: WithFallback<u8, u16> = serde_json::from_str(json).unwrap();
+/// assert_eq!(delegate.evaluate(2), 20);
+/// assert_eq!(delegate.evaluate(50), 5000);
+/// ```
+#[derive(Serialize)]
+pub struct WithFallback<X, Y, E = expression::Evaluate>
+where
+    X: PartialEq + Eq + Hash,
+{
+    values: Evaluate<X, Y>,
+    fallback: Fallback<Y, E>,
+    /// Inputs of the map, increasing
+    #[serde(skip)]
+    keys: Vec<(f64, X)>,
+}
+
+impl<X, Y, E> WithFallback<X, Y, E>
+where
+    X: PartialEq + Eq + Hash,
+{
+    pub fn values(&self) -> &Evaluate<X, Y> {
+        &self.values
+    }
+
+    pub fn fallback(&self) -> &Fallback<Y, E> {
+        &self.fallback
+    }
+
+    /// Entries of the map ordered by their distance to `x`, the smaller input on ties
+    fn closest(&self, x: f64) -> impl Iterator<Item = (f64, Y)> + '_
+    where
+        Y: Clone + Copy,
+    {
+        let split = self.keys.partition_point(|(key, _)| *key < x);
+        let mut before = self.keys[..split].iter().rev().peekable();
+        let mut after = self.keys[split..].iter().peekable();
+        iter::from_fn(move || {
+            let (position, key) = match (before.peek(), after.peek()) {
+                (Some((a, _)), Some((b, _))) if b - x < x - a => after.next(),
+                (Some(_), _) => before.next(),
+                (None, _) => after.next(),
+            }?;
+            Some((*position, self.values.0[key]))
+        })
+    }
+}
+
+impl<'de, X, Y, E> Deserialize<'de> for WithFallback<X, Y, E>
+where
+    X: Clone + Copy + Eq + Hash + ToPrimitive + Deserialize<'de>,
+    Y: Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(bound(
+            deserialize = "X: Eq + Hash + Deserialize<'de>, Y: Deserialize<'de>, E: Deserialize<'de>"
+        ))]
+        struct Fields<X, Y, E>
+        where
+            X: Eq + Hash,
+        {
+            values: Evaluate<X, Y>,
+            fallback: Fallback<Y, E>,
+        }
+        let Fields { values, fallback } = Fields::deserialize(deserializer)?;
+        Ok(values.with_fallback(fallback))
+    }
+}
+
+impl<X, Y, E> super::Evaluate<X, Y> for WithFallback<X, Y, E>
+where
+    X: PartialEq + Eq + Hash + ToPrimitive + FromPrimitive,
+    Y: Clone + Copy + ToPrimitive + FromPrimitive,
+    E: super::Evaluate<X, Y>,
+{
+    fn evaluate(&self, x: X) -> Y {
+        if let Some(value) = self.values.0.get(&x) {
+            return *value;
+        }
+        let position = x.to_f64().expect("Cannot convert X to f64");
+        match &self.fallback {
+            Fallback::Clamp => {
+                self.closest(position)
+                    .next()
+                    .expect("Cannot clamp to an empty ValueMap")
+                    .1
+            }
+            Fallback::Extrapolate => {
+                assert!(
+                    self.keys.len() >= 2,
+                    "Cannot extrapolate from fewer than two keys"
+                );
+                let split = self.keys.partition_point(|(key, _)| *key < position);
+                let upper = split.clamp(1, self.keys.len() - 1);
+                let (x0, key0) = &self.keys[upper - 1];
+                let (x1, key1) = &self.keys[upper];
+                let y0 = self.values.0[key0]
+                    .to_f64()
+                    .expect("Cannot convert Y to f64");
+                let y1 = self.values.0[key1]
+                    .to_f64()
+                    .expect("Cannot convert Y to f64");
+                Y::from_f64(y0 + (y1 - y0) * (position - x0) / (x1 - x0))
+                    .expect("Cannot convert f64 to Y")
+            }
+            Fallback::Cycle => {
+                let (min, _) = self.keys.first().expect("Cannot cycle an empty ValueMap");
+                let index = (position - min).floor().rem_euclid(self.keys.len() as f64) as usize;
+                self.values.0[&self.keys[index].1]
+            }
+            Fallback::Default(value) => *value,
+            Fallback::Delegate(evaluate) => evaluate.evaluate(x),
+        }
+    }
+}
@@ -16,6 +16,7 @@ pub struct Growth<Lvl, Exp, Eval>
 where
     Eval: Evaluate<Lvl, Exp>,
 {
+    #[serde(skip)]
     lvl: PhantomData<Lvl>,
     evaluation: Eval,
     progress: Progress<Exp>,